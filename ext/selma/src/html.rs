@@ -7,6 +7,7 @@ pub(crate) struct SelmaHTML {}
 pub fn init(m_selma: RModule) -> Result<(), Error> {
     let c_html = m_selma.define_class("HTML", Default::default()).unwrap();
 
+    doctype::init(c_html).expect("cannot define Selma::HTML::Doctype class");
     element::init(c_html).expect("cannot define Selma::HTML::Element class");
     end_tag::init(c_html).expect("cannot define Selma::HTML::EndTag class");
     text_chunk::init(c_html).expect("cannot define Selma::HTML::TextChunk class");
@@ -14,6 +15,7 @@ pub fn init(m_selma: RModule) -> Result<(), Error> {
     Ok(())
 }
 
+pub mod doctype;
 pub mod element;
 pub mod end_tag;
 pub mod text_chunk;
@@ -0,0 +1,95 @@
+use crate::native_ref_wrap::NativeRefWrap;
+use lol_html::html_content::Doctype;
+use magnus::{exception, method, Error, Module, RClass};
+
+struct HTMLDoctype {
+    doctype: NativeRefWrap<Doctype<'static>>,
+}
+
+#[magnus::wrap(class = "Selma::HTML::Doctype")]
+pub struct SelmaHTMLDoctype(std::cell::RefCell<HTMLDoctype>);
+
+/// SAFETY: This is safe because we only access this data when the GVL is held.
+unsafe impl Send for SelmaHTMLDoctype {}
+
+impl SelmaHTMLDoctype {
+    pub fn new(doctype: &mut Doctype) -> Self {
+        let (ref_wrap, _anchor) = NativeRefWrap::wrap_mut(doctype);
+
+        Self(std::cell::RefCell::new(HTMLDoctype { doctype: ref_wrap }))
+    }
+
+    fn name(&self) -> Result<Option<String>, Error> {
+        let binding = self.0.borrow();
+
+        if let Ok(d) = binding.doctype.get() {
+            Ok(d.name())
+        } else {
+            Err(Error::new(
+                exception::runtime_error(),
+                "`name` is not available",
+            ))
+        }
+    }
+
+    fn public_id(&self) -> Result<Option<String>, Error> {
+        let binding = self.0.borrow();
+
+        if let Ok(d) = binding.doctype.get() {
+            Ok(d.public_id())
+        } else {
+            Err(Error::new(
+                exception::runtime_error(),
+                "`public_id` is not available",
+            ))
+        }
+    }
+
+    fn system_id(&self) -> Result<Option<String>, Error> {
+        let binding = self.0.borrow();
+
+        if let Ok(d) = binding.doctype.get() {
+            Ok(d.system_id())
+        } else {
+            Err(Error::new(
+                exception::runtime_error(),
+                "`system_id` is not available",
+            ))
+        }
+    }
+
+    fn remove(&self) {
+        let mut binding = self.0.borrow_mut();
+
+        if let Ok(d) = binding.doctype.get_mut() {
+            d.remove()
+        }
+    }
+
+    fn is_removed(&self) -> Result<bool, Error> {
+        let binding = self.0.borrow();
+
+        if let Ok(d) = binding.doctype.get() {
+            Ok(d.removed())
+        } else {
+            Err(Error::new(
+                exception::runtime_error(),
+                "`is_removed` is not available",
+            ))
+        }
+    }
+}
+
+pub fn init(c_html: RClass) -> Result<(), Error> {
+    let c_doctype = c_html
+        .define_class("Doctype", Default::default())
+        .expect("cannot find class Selma::HTML::Doctype");
+
+    c_doctype.define_method("name", method!(SelmaHTMLDoctype::name, 0))?;
+    c_doctype.define_method("public_id", method!(SelmaHTMLDoctype::public_id, 0))?;
+    c_doctype.define_method("system_id", method!(SelmaHTMLDoctype::system_id, 0))?;
+    c_doctype.define_method("remove", method!(SelmaHTMLDoctype::remove, 0))?;
+    c_doctype.define_method("removed?", method!(SelmaHTMLDoctype::is_removed, 0))?;
+
+    Ok(())
+}
@@ -1,15 +1,31 @@
 use lol_html::{
-    doc_comments, doctype, element,
-    html_content::{Element, TextChunk},
+    doc_comments, doc_text, doctype, element,
+    html_content::{ContentType, Doctype, Element, TextChunk},
     text, DocumentContentHandlers, ElementContentHandlers, HtmlRewriter, Selector, Settings,
 };
-use magnus::{exception, function, method, scan_args, Module, Object, RArray, RModule, Value};
+use magnus::{
+    exception, function, method, scan_args, Module, Object, RArray, RModule, Symbol, Value,
+};
 
-use std::{borrow::Cow, cell::RefCell, primitive::str, rc::Rc};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    primitive::str,
+    rc::Rc,
+};
 
 use crate::{
-    html::{element::SelmaHTMLElement, end_tag::SelmaHTMLEndTag, text_chunk::SelmaHTMLTextChunk},
-    sanitizer::SelmaSanitizer,
+    html::{
+        doctype::SelmaHTMLDoctype, element::SelmaHTMLElement, end_tag::SelmaHTMLEndTag,
+        text_chunk::SelmaHTMLTextChunk,
+    },
+    sanitizer::{
+        document::{DocumentSkeleton, SanitizeMode},
+        empty_elements::PendingElements,
+        foreign::{ForeignContent, Namespace},
+        tagfilter::gfm_tagfilter,
+        SelmaSanitizer,
+    },
     selector::SelmaSelector,
     tags::Tag,
     wrapped_struct::WrappedStruct,
@@ -30,6 +46,7 @@ pub struct Handler {
 pub struct Rewriter {
     sanitizer: Option<SelmaSanitizer>,
     handlers: Vec<Handler>,
+    mode: Option<SanitizeMode>,
 
     total_elapsed: f64,
 }
@@ -41,14 +58,32 @@ impl SelmaRewriter {
     const SELMA_ON_END_TAG: &str = "on_end_tag";
     const SELMA_HANDLE_ELEMENT: &str = "handle_element";
     const SELMA_HANDLE_TEXT_CHUNK: &str = "handle_text_chunk";
+    const SELMA_HANDLE_DOCTYPE: &str = "handle_doctype";
+
+    const HTML5_DOCTYPE: &str = "<!DOCTYPE html>";
 
     /// @yard
-    /// @def new(sanitizer: Selma::Sanitizer.new(Selma::Sanitizer::Config::DEFAULT), handlers: [])
+    /// @def new(sanitizer: Selma::Sanitizer.new(Selma::Sanitizer::Config::DEFAULT), handlers: [], mode: nil)
     /// @param sanitizer [Selma::Sanitizer] The sanitizer which performs the initial cleanup
     /// @param handlers  [Array<Selma::Selector>] The handlers to use to perform HTML rewriting
+    /// @param mode      [Symbol, nil] `:fragment` or `:document`, for how the sanitizer treats `html`, `head`, `body`, and doctypes
     /// @return [Selma::Rewriter]
     fn new(args: &[Value]) -> Result<Self, magnus::Error> {
-        let (rb_sanitizer, rb_handlers) = Self::scan_parse_args(args)?;
+        let (rb_sanitizer, rb_handlers, rb_mode) = Self::scan_parse_args(args)?;
+
+        let mode = match rb_mode {
+            None => None,
+            Some(mode) => match mode.name()?.as_ref() {
+                "fragment" => Some(SanitizeMode::Fragment),
+                "document" => Some(SanitizeMode::Document),
+                other => {
+                    return Err(magnus::Error::new(
+                        exception::arg_error(),
+                        format!("unknown mode `:{other}`; expected `:fragment` or `:document`"),
+                    ));
+                }
+            },
+        };
 
         let sanitizer = match rb_sanitizer {
             None => {
@@ -121,6 +156,7 @@ impl SelmaRewriter {
         Ok(Self(std::cell::RefCell::new(Rewriter {
             sanitizer,
             handlers,
+            mode,
             total_elapsed: 0.0,
         })))
     }
@@ -132,6 +168,7 @@ impl SelmaRewriter {
         (
             Option<Option<WrappedStruct<SelmaSanitizer>>>,
             Option<RArray>,
+            Option<Symbol>,
         ),
         magnus::Error,
     > {
@@ -148,20 +185,22 @@ impl SelmaRewriter {
             (
                 Option<Option<WrappedStruct<SelmaSanitizer>>>,
                 Option<RArray>,
+                Option<Symbol>,
             ),
             (),
-        >(args.keywords, &[], &["sanitizer", "handlers"])?;
-        let (rb_sanitizer, rb_handlers) = kwargs.optional;
+        >(args.keywords, &[], &["sanitizer", "handlers", "mode"])?;
+        let (rb_sanitizer, rb_handlers, rb_mode) = kwargs.optional;
 
-        Ok((rb_sanitizer, rb_handlers))
+        Ok((rb_sanitizer, rb_handlers, rb_mode))
     }
 
     /// Perform HTML rewrite sequence.
     fn rewrite(&self, html: String) -> Result<String, magnus::Error> {
+        let mode = self.0.borrow().mode;
         let sanitized_html = match &self.0.borrow().sanitizer {
             None => Ok(html),
             Some(sanitizer) => {
-                let sanitized_html = match Self::perform_sanitization(sanitizer, &html) {
+                let sanitized_html = match Self::perform_sanitization(sanitizer, &html, mode) {
                     Ok(sanitized_html) => sanitized_html,
                     Err(err) => return Err(err),
                 };
@@ -172,82 +211,201 @@ impl SelmaRewriter {
         let binding = self.0.borrow_mut();
         let handlers = &binding.handlers;
 
+        // without handlers, there's no need to parse the output again
+        if handlers.is_empty() {
+            return Ok(sanitized_html.unwrap());
+        }
+
         match Self::perform_handler_rewrite(self, handlers, sanitized_html.unwrap()) {
             Ok(rewritten_html) => Ok(String::from_utf8(rewritten_html).unwrap()),
             Err(err) => Err(err),
         }
     }
 
-    fn perform_sanitization(
+    /// Without a `mode`, `html`, `head`, `body`, and doctypes are sanitized
+    /// like anything else.
+    pub fn perform_sanitization(
         sanitizer: &SelmaSanitizer,
-        html: &String,
+        html: &str,
+        mode: Option<SanitizeMode>,
     ) -> Result<Vec<u8>, magnus::Error> {
-        let mut first_pass_html = vec![];
+        // the tagfilter works on the markup as written, so it has to run
+        // before anything is parsed
+        let html = if sanitizer.get_gfm_tagfilter() {
+            gfm_tagfilter(html)
+        } else {
+            Cow::Borrowed(html)
+        };
+
+        let mut output = vec![];
+        // doctypes can't be rewritten, so a normalized one is written before
+        // whatever comes next instead, or at the end, if nothing does
+        let pending_doctype = Cell::new(false);
+        // raw text, like a `<script>`'s, would be parsed as markup once its
+        // element is gone, so it's escaped instead; `Some(true)` when its
+        // character references should be decoded first
+        let escaped_raw_text: Rc<Cell<Option<bool>>> = Rc::new(Cell::new(None));
+        // elements that may be left empty hold back their start tags until
+        // either content or their end tag shows up
+        let removes_empty_elements = sanitizer.removes_empty_elements();
+        let whitespace_is_empty = sanitizer.get_remove_whitespace_only_elements();
+        let pending = Rc::new(RefCell::new(PendingElements::default()));
+        // how many elements being removed along with their contents the
+        // parser is in, since lol_html still hands over what's in them
+        let removed_content_depth = Rc::new(Cell::new(0_usize));
+        // a document's `<html>`, `<head>`, and `<body>` are filled in as it's
+        // sanitized, from whatever makes it into the output
+        let skeleton = (mode == Some(SanitizeMode::Document))
+            .then(|| Rc::new(RefCell::new(DocumentSkeleton::default())));
         {
             let mut document_content_handlers: Vec<DocumentContentHandlers> = vec![];
-            if !sanitizer.get_allow_doctype() {
+            if mode == Some(SanitizeMode::Fragment) {
                 document_content_handlers.push(doctype!(|d| {
-                    sanitizer.remove_doctype(d);
+                    d.remove();
                     Ok(())
                 }));
-            }
-            if !sanitizer.get_allow_comments() {
-                document_content_handlers.push(doc_comments!(|c| {
-                    sanitizer.remove_comment(c);
+            } else if sanitizer.sanitizes_doctypes() {
+                document_content_handlers.push(doctype!(|d| {
+                    if sanitizer.sanitize_doctype(d) {
+                        pending_doctype.set(true);
+                    }
+                    Ok(())
+                }));
+                document_content_handlers.push(doc_text!(|t| {
+                    if pending_doctype.replace(false) {
+                        t.before(Self::HTML5_DOCTYPE, ContentType::Html);
+                    }
                     Ok(())
                 }));
             }
-            let mut rewriter = HtmlRewriter::new(
-                Settings {
-                    document_content_handlers,
-                    element_content_handlers: vec![element!("*", |el| {
-                        sanitizer.try_remove_element(el);
-                        if el.removed() {
-                            return Ok(());
-                        }
-                        match sanitizer.sanitize_attributes(el) {
-                            Ok(_) => Ok(()),
-                            Err(err) => Err(err.to_string().into()),
-                        }
-                    })],
-                    // TODO: allow for MemorySettings to be defined
-                    ..Settings::default()
-                },
-                |c: &[u8]| first_pass_html.extend_from_slice(c),
-            );
+            document_content_handlers.push(doc_comments!(|c| {
+                if pending_doctype.replace(false) {
+                    c.before(Self::HTML5_DOCTYPE, ContentType::Html);
+                }
+                sanitizer.sanitize_comment(c);
+                if removes_empty_elements && !c.removed() && removed_content_depth.get() == 0 {
+                    let mut pending = pending.borrow_mut();
+                    if pending.is_holding() {
+                        pending.hold_comment(&format!("<!--{}-->", c.text()));
+                        c.remove();
+                    }
+                }
+                Ok(())
+            }));
+            document_content_handlers.push(doc_text!(|t| {
+                if let Some(decode) = escaped_raw_text.get() {
+                    let text = if decode {
+                        String::from_utf8_lossy(&escapist::unescape_html(t.as_str().as_bytes()))
+                            .to_string()
+                    } else {
+                        t.as_str().to_string()
+                    };
+                    t.replace(&text, ContentType::Text);
+                }
+                if let Some(skeleton) = &skeleton {
+                    if removed_content_depth.get() == 0 {
+                        skeleton.borrow_mut().text(t);
+                    }
+                }
+                if removes_empty_elements && removed_content_depth.get() == 0 {
+                    let text = t.as_str();
+                    let is_content = if whitespace_is_empty {
+                        !text.trim().is_empty()
+                    } else {
+                        !text.is_empty()
+                    };
+                    let mut pending = pending.borrow_mut();
+                    if is_content {
+                        let start_tags = pending.flush();
+                        t.before(&start_tags, ContentType::Html);
+                    } else if pending.is_holding() {
+                        pending.hold_text(text);
+                        t.remove();
+                    }
+                }
+                Ok(())
+            }));
+            // `<style>` text arrives in chunks, so it's buffered until the
+            // whole stylesheet can be sanitized at once
+            let mut stylesheet = String::new();
+            let foreign_content = Rc::new(RefCell::new(ForeignContent::default()));
+            let mut element_content_handlers = vec![element!("*", |el| {
+                if pending_doctype.replace(false) {
+                    el.before(Self::HTML5_DOCTYPE, ContentType::Html);
+                }
+                // lol_html removes everything inside a removed element itself
+                let in_removed_content = el.removed();
+                if in_removed_content {
+                    let result = Self::sanitize_element(
+                        sanitizer,
+                        el,
+                        mode,
+                        &foreign_content,
+                        &escaped_raw_text,
+                    );
+                    sanitizer.take_removed_contents();
+                    return result;
+                }
+                removed_content_depth.set(0);
 
-            let result = rewriter.write(html.as_bytes());
-            if result.is_err() {
-                return Err(magnus::Error::new(
-                    exception::runtime_error(),
-                    format!("Failed to sanitize HTML: {}", result.unwrap_err()),
-                ));
-            }
-        }
+                // an escaped element's tags are content, and are written
+                // before it, so anything held back has to be written first
+                if sanitizer.should_escape(el) && sanitizer.allow_element(el) {
+                    if let Some(skeleton) = &skeleton {
+                        skeleton.borrow_mut().escape(el);
+                    }
+                    if removes_empty_elements {
+                        let start_tags = pending.borrow_mut().flush();
+                        el.before(&start_tags, ContentType::Html);
+                    }
+                }
 
-        let mut output = vec![];
-        {
-            let mut element_content_handlers: Vec<(Cow<Selector>, ElementContentHandlers)> = vec![];
-            if sanitizer.get_escape_tagfilter() {
-                element_content_handlers.push(element!(Tag::ESCAPEWORTHY_TAGS_CSS, |el| {
-                    let should_remove = sanitizer.allow_element(el);
-                    if should_remove {
-                        sanitizer.force_remove_element(el);
+                Self::sanitize_element(sanitizer, el, mode, &foreign_content, &escaped_raw_text)?;
+
+                if let Some(skeleton) = &skeleton {
+                    if !el.removed() {
+                        DocumentSkeleton::enter(skeleton, el);
                     }
+                }
 
+                if sanitizer.take_removed_contents() {
+                    let end_tag_depth = Rc::clone(&removed_content_depth);
+                    if let Some(end_tag_handlers) = el.end_tag_handlers() {
+                        end_tag_depth.set(end_tag_depth.get() + 1);
+                        end_tag_handlers.push(Box::new(move |_end| {
+                            end_tag_depth.set(end_tag_depth.get().saturating_sub(1));
+                            Ok(())
+                        }));
+                    }
+                } else if removes_empty_elements && !el.removed() {
+                    Self::hold_if_empty(sanitizer, el, &pending);
+                }
+                Ok(())
+            })];
+            if sanitizer.sanitizes_style_elements() {
+                element_content_handlers.push(text!("style", |t| {
+                    stylesheet.push_str(t.as_str());
+                    if t.last_in_text_node() {
+                        let sanitized_css = sanitizer.sanitize_stylesheet(&stylesheet);
+                        t.replace(&sanitized_css, ContentType::Html);
+                        stylesheet.clear();
+                    } else {
+                        t.remove();
+                    }
                     Ok(())
                 }));
             }
-
             let mut rewriter = HtmlRewriter::new(
                 Settings {
+                    document_content_handlers,
                     element_content_handlers,
+                    // TODO: allow for MemorySettings to be defined
                     ..Settings::default()
                 },
                 |c: &[u8]| output.extend_from_slice(c),
             );
 
-            let result = rewriter.write(first_pass_html.as_slice());
+            let result = rewriter.write(html.as_bytes());
             if result.is_err() {
                 return Err(magnus::Error::new(
                     exception::runtime_error(),
@@ -255,10 +413,92 @@ impl SelmaRewriter {
                 ));
             }
         }
+        if pending_doctype.get() {
+            output.extend_from_slice(Self::HTML5_DOCTYPE.as_bytes());
+        }
+
+        if let Some(skeleton) = skeleton {
+            output.extend_from_slice(skeleton.borrow_mut().finish().as_bytes());
+        }
 
         Ok(output)
     }
 
+    /// Sanitizes an element on its way through the rewriter, removing it if
+    /// it isn't allowed.
+    fn sanitize_element(
+        sanitizer: &SelmaSanitizer,
+        el: &mut Element,
+        mode: Option<SanitizeMode>,
+        foreign_content: &Rc<RefCell<ForeignContent>>,
+        escaped_raw_text: &Rc<Cell<Option<bool>>>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if mode == Some(SanitizeMode::Fragment) && sanitizer.strip_document_element(el) {
+            return Ok(());
+        }
+        let placement = ForeignContent::enter(foreign_content, el);
+        if placement.is_foreign() {
+            match sanitizer.sanitize_foreign_element(el, &placement) {
+                Ok(true) => return Ok(()),
+                Ok(false) => {}
+                Err(err) => return Err(err.to_string().into()),
+            }
+        }
+        // `<title>` in SVG is up to the SVG allowlist
+        let is_html = placement.namespace == Namespace::Html;
+        if is_html && sanitizer.escape_tagfilter(el) {
+            return Ok(());
+        }
+        sanitizer.try_remove_element(el);
+        if el.removed() {
+            let tag = Tag::tag_from_element(el);
+            if is_html && Tag::has_raw_text(tag) {
+                escaped_raw_text.set(Some(Tag::has_escapable_raw_text(tag)));
+
+                let end_tag_escaped_raw_text = Rc::clone(escaped_raw_text);
+                if let Some(end_tag_handlers) = el.end_tag_handlers() {
+                    end_tag_handlers.push(Box::new(move |_end| {
+                        end_tag_escaped_raw_text.set(None);
+                        Ok(())
+                    }));
+                }
+            }
+            return Ok(());
+        }
+        match sanitizer.sanitize_attributes(el) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err.to_string().into()),
+        }
+    }
+
+    /// Holds back the start tag of an element that's dropped if it's left
+    /// empty, or else writes out whatever was held back, since the element
+    /// is content.
+    fn hold_if_empty(
+        sanitizer: &SelmaSanitizer,
+        el: &mut Element,
+        pending: &Rc<RefCell<PendingElements>>,
+    ) {
+        if !sanitizer.should_remove_if_empty(el) || el.end_tag_handlers().is_none() {
+            let start_tags = pending.borrow_mut().flush();
+            el.before(&start_tags, ContentType::Html);
+            return;
+        }
+
+        let id = pending.borrow_mut().push(el);
+        el.remove_and_keep_content();
+
+        let end_tag_pending = Rc::clone(pending);
+        if let Some(end_tag_handlers) = el.end_tag_handlers() {
+            end_tag_handlers.push(Box::new(move |end| {
+                if let Some(end_tag) = end_tag_pending.borrow_mut().close(id) {
+                    end.before(&end_tag, ContentType::Html);
+                }
+                Ok(())
+            }));
+        }
+    }
+
     pub fn perform_handler_rewrite(
         &self,
         handlers: &[Handler],
@@ -266,8 +506,23 @@ impl SelmaRewriter {
     ) -> Result<Vec<u8>, magnus::Error> {
         // TODO: this should ideally be done ahead of time, not on every `#rewrite` call
         let mut element_content_handlers: Vec<(Cow<Selector>, ElementContentHandlers)> = vec![];
+        let mut document_content_handlers: Vec<DocumentContentHandlers> = vec![];
 
         handlers.iter().for_each(|handler| {
+            // doctypes aren't matched by selectors, so any handler can ask for them
+            if handler
+                .rb_handler
+                .respond_to(Self::SELMA_HANDLE_DOCTYPE, true)
+                .unwrap()
+            {
+                document_content_handlers.push(doctype!(move |d| {
+                    match Self::process_doctype_handlers(handler.rb_handler, d) {
+                        Ok(_) => Ok(()),
+                        Err(err) => Err(err.to_string().into()),
+                    }
+                }));
+            }
+
             let element_stack: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(vec![]));
 
             let selector = handler.rb_selector.get_static().unwrap();
@@ -348,6 +603,7 @@ impl SelmaRewriter {
         {
             let mut rewriter = HtmlRewriter::new(
                 Settings {
+                    document_content_handlers,
                     element_content_handlers,
                     ..Settings::default()
                 },
@@ -396,6 +652,17 @@ impl SelmaRewriter {
         }
     }
 
+    fn process_doctype_handlers(
+        rb_handler: Value,
+        doctype: &mut Doctype,
+    ) -> Result<(), magnus::Error> {
+        let rb_doctype = SelmaHTMLDoctype::new(doctype);
+        match rb_handler.funcall::<_, _, Value>(Self::SELMA_HANDLE_DOCTYPE, (rb_doctype,)) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
     fn process_text_handlers(
         rb_handler: Value,
         text_chunk: &mut TextChunk,
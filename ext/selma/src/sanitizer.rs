@@ -1,42 +1,291 @@
-use std::{borrow::BorrowMut, collections::HashMap};
+use std::{borrow::BorrowMut, cell::Cell, collections::HashMap};
 
 use lol_html::{
     errors::AttributeNameError,
     html_content::{Comment, ContentType, Doctype, Element, EndTag},
 };
-use magnus::{class, function, method, scan_args, Module, Object, RArray, RHash, RModule, Value};
+use magnus::{
+    class, exception, function, method, scan_args, Module, Object, RArray, RHash, RModule, Symbol,
+    Value,
+};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rewriter::SelmaRewriter,
+    sanitizer::{
+        classes::ClassAllowlist,
+        css::CssSanitizer,
+        data_uri::DataUriPolicy,
+        document::SanitizeMode,
+        foreign::{ForeignSanitizer, Namespace, Placement},
+        head::HeadSanitizer,
+        links::{LinkSanitizer, RelMode},
+        policy::Policy,
+        url::BaseUrl,
+    },
+    wrapped_struct::WrappedStruct,
+};
+
+/// What to do with an element once none of its required attributes survive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum RequiredAttributeAction {
+    /// Drop the element, along with everything inside it.
+    Remove,
+    /// Drop the element's tags, but keep its contents.
+    Unwrap,
+}
+
+/// How allowed doctypes are treated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DoctypePolicy {
+    /// Keep doctypes as they are.
+    Keep,
+    /// Write every doctype as `<!DOCTYPE html>`.
+    Normalize,
+    /// Drop doctypes with public or system identifiers, or with a name other
+    /// than `html`.
+    RejectLegacy,
+}
+
+/// Constraints an allowed attribute's value must meet to be kept.
+#[derive(Clone, Debug, Default)]
+struct AttributeValueConstraint {
+    values: Option<Vec<String>>,
+    pattern: Option<Regex>,
+    /// The pattern as it was given, and whether it's case-insensitive.
+    pattern_source: Option<(String, bool)>,
+    max_length: Option<usize>,
+}
+
+impl AttributeValueConstraint {
+    fn allows(&self, value: &str) -> bool {
+        if let Some(values) = &self.values {
+            if !values.iter().any(|v| v == value) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(value) {
+                return false;
+            }
+        }
+
+        if let Some(max_length) = self.max_length {
+            if value.chars().count() > max_length {
+                return false;
+            }
+        }
+
+        true
+    }
+}
 
 #[derive(Clone, Debug)]
 struct ElementSanitizer {
     allowed_attrs: Vec<String>,
     required_attrs: Vec<String>,
-    allowed_classes: Vec<String>,
+    required_attrs_action: RequiredAttributeAction,
+    allowed_classes: ClassAllowlist,
     protocol_sanitizers: HashMap<String, Vec<String>>,
+    value_constraints: HashMap<String, AttributeValueConstraint>,
+    /// Which `data:` URLs each attribute keeps, whatever its protocols.
+    data_uri_policies: HashMap<String, DataUriPolicy>,
 }
 
 impl Default for ElementSanitizer {
     fn default() -> Self {
         ElementSanitizer {
             allowed_attrs: vec![],
-            allowed_classes: vec![],
+            allowed_classes: ClassAllowlist::default(),
             required_attrs: vec![],
+            required_attrs_action: RequiredAttributeAction::Unwrap,
 
             protocol_sanitizers: HashMap::new(),
+            value_constraints: HashMap::new(),
+            data_uri_policies: HashMap::new(),
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Sanitizer {
-    flags: [u8; crate::tags::Tag::TAG_COUNT],
+    /// One set of flags per HTML tag, followed by one per custom element.
+    flags: Vec<u8>,
+    /// Custom elements, like `x-emoji`, and prefixes, like `github-*`, in the
+    /// order they were added. Each has its own flags, after the HTML tags'.
+    custom_elements: Vec<String>,
     allowed_attrs: Vec<String>,
-    allowed_classes: Vec<String>,
+    allowed_classes: ClassAllowlist,
     element_sanitizers: HashMap<String, ElementSanitizer>,
+    css: Option<CssSanitizer>,
+    links: Option<LinkSanitizer>,
+    /// Rules for `<meta>`, `<base>`, and `<link>`. Without them, the defaults
+    /// still apply.
+    head: Option<HeadSanitizer>,
+    id_prefix: Option<String>,
+    /// Relative URLs are resolved against this before their protocols are
+    /// checked.
+    base_url: Option<BaseUrl>,
+    remove_whitespace_only_elements: bool,
+    /// Allowlists for `<svg>` and `<math>` contents. Without one, elements in
+    /// that namespace are sanitized like any other.
+    svg: Option<ForeignSanitizer>,
+    math: Option<ForeignSanitizer>,
 
     pub escape_tagfilter: bool,
+    /// Whether filtered tags are escaped the way GitHub Flavored Markdown's
+    /// tagfilter does, before anything else is sanitized.
+    gfm_tagfilter: bool,
     pub allow_comments: bool,
+    /// When set, only comments matching this are kept.
+    comment_pattern: Option<Regex>,
+    /// The comment pattern as it was given, and whether it's case-insensitive.
+    comment_pattern_source: Option<(String, bool)>,
     pub allow_doctype: bool,
-    config: RHash,
+    doctype_policy: DoctypePolicy,
+    /// Set whenever an element is removed along with its contents, until the
+    /// rewriter takes it, so that it knows they won't be written.
+    removed_contents: Cell<bool>,
+    /// `None` for sanitizers built from a policy document.
+    config: Option<RHash>,
+    /// Whether the config has been applied, or there's none to apply.
+    configured: bool,
+}
+
+impl Sanitizer {
+    /// Names of the elements with `flag` set, in tag order, followed by the
+    /// custom elements in the order they were added.
+    fn elements_with(&self, flag: u8) -> Vec<String> {
+        let html_elements = crate::tags::Tag::html_tags()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| *index != crate::tags::HTMLTag::UNKNOWN as usize)
+            .filter(|(index, _)| self.flags[*index] & flag != 0)
+            .map(|(_, html_tag)| crate::tags::Tag::element_name_from_enum(&html_tag).to_string());
+        let custom_elements = self
+            .custom_elements
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.flags[crate::tags::Tag::TAG_COUNT + index] & flag != 0)
+            .map(|(_, name)| name.clone());
+
+        html_elements.chain(custom_elements).collect()
+    }
+
+    /// Whether every element, including those nobody configured, has `flag` set.
+    fn all_elements_have(&self, flag: u8) -> bool {
+        self.flags.iter().all(|flags| flags & flag != 0)
+    }
+
+    /// The custom element entry `tag_name` falls under: its own, or else the
+    /// longest prefix it matches.
+    fn custom_element(&self, tag_name: &str) -> Option<usize> {
+        if let Some(index) = self
+            .custom_elements
+            .iter()
+            .position(|name| name == tag_name)
+        {
+            return Some(index);
+        }
+
+        self.custom_elements
+            .iter()
+            .enumerate()
+            .filter_map(|(index, name)| Some((index, name.strip_suffix('*')?)))
+            .filter(|(_, prefix)| tag_name.starts_with(prefix) && tag_name.len() > prefix.len())
+            .max_by_key(|(_, prefix)| prefix.len())
+            .map(|(index, _)| index)
+    }
+
+    /// Where `tag_name`'s flags are. Elements that are neither HTML tags nor
+    /// custom elements share the flags of `HTMLTag::UNKNOWN`.
+    fn flags_index(&self, tag_name: &str) -> usize {
+        let tag = crate::tags::Tag::tag_from_tag_name(tag_name);
+        if tag.index != crate::tags::HTMLTag::UNKNOWN as usize {
+            return tag.index;
+        }
+
+        self.custom_element(tag_name)
+            .map_or(tag.index, |index| crate::tags::Tag::TAG_COUNT + index)
+    }
+
+    /// Whether `attr_name` holds a single URL to resolve against the base URL.
+    fn resolves_urls_in(&self, attr_name: &str) -> bool {
+        // `ping` holds a list of URLs
+        self.base_url.is_some() && url::URL_ATTRIBUTES.contains(&attr_name) && attr_name != "ping"
+    }
+
+    fn resolve_url(&self, url: &str) -> String {
+        match &self.base_url {
+            Some(base_url) => base_url.resolve(url),
+            None => url.to_string(),
+        }
+    }
+
+    fn flags_for(&self, tag_name: &str) -> u8 {
+        self.flags[self.flags_index(tag_name)]
+    }
+
+    /// Like `flags_index`, except that a custom element, or a prefix of them,
+    /// gets flags of its own, starting from those of `HTMLTag::UNKNOWN`. Other
+    /// names, like SVG's `circle`, share the flags of `HTMLTag::UNKNOWN`.
+    fn flags_index_mut(&mut self, element_name: &str) -> Result<usize, magnus::Error> {
+        let tag = crate::tags::Tag::tag_from_tag_name(element_name);
+        if tag.index != crate::tags::HTMLTag::UNKNOWN as usize {
+            return Ok(tag.index);
+        }
+
+        if !policy::is_element_name(element_name) {
+            return Err(magnus::Error::new(
+                exception::arg_error(),
+                format!(
+                    "invalid element name {element_name:?}; names with a `-` must be valid custom element names"
+                ),
+            ));
+        }
+
+        if !element_name.contains('-') {
+            return Ok(tag.index);
+        }
+
+        match self
+            .custom_elements
+            .iter()
+            .position(|name| name == element_name)
+        {
+            Some(index) => Ok(crate::tags::Tag::TAG_COUNT + index),
+            None => {
+                self.custom_elements.push(element_name.to_string());
+                self.flags.push(self.flags[tag.index]);
+                Ok(self.flags.len() - 1)
+            }
+        }
+    }
+
+    /// The name `tag_name`'s element sanitizer is kept under, which is that
+    /// of the prefix it matches, if it's a custom element with one.
+    fn element_sanitizer_name<'a>(&'a self, tag_name: &'a str) -> &'a str {
+        if crate::tags::Tag::tag_from_tag_name(tag_name).index
+            != crate::tags::HTMLTag::UNKNOWN as usize
+        {
+            return tag_name;
+        }
+
+        self.custom_element(tag_name)
+            .map_or(tag_name, |index| &self.custom_elements[index])
+    }
+
+    fn foreign_sanitizer(&self, namespace: Namespace) -> Option<&ForeignSanitizer> {
+        match namespace {
+            Namespace::Html => None,
+            Namespace::Svg => self.svg.as_ref(),
+            Namespace::MathMl => self.math.as_ref(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -48,17 +297,36 @@ impl SelmaSanitizer {
     // const SELMA_SANITIZER_ESCAPE_TAGFILTER: u8 = (1 << 1);
     const SELMA_SANITIZER_REMOVE_CONTENTS: u8 = (1 << 2);
     const SELMA_SANITIZER_WRAP_WHITESPACE: u8 = (1 << 3);
+    const SELMA_SANITIZER_REMOVE_IF_EMPTY: u8 = (1 << 4);
+    const SELMA_SANITIZER_ESCAPE: u8 = (1 << 5);
 
     pub fn new(arguments: &[Value]) -> Result<Self, magnus::Error> {
         let args = scan_args::scan_args::<(), (Option<RHash>,), (), (), (), ()>(arguments)?;
         let (opt_config,): (Option<RHash>,) = args.optional;
 
         let config = match opt_config {
-            Some(config) => config,
+            Some(config) => {
+                let config_module: Value = magnus::eval(r#"Selma::Sanitizer::Config"#)?;
+                config_module.funcall::<_, _, Value>("validate!", (config,))?;
+                config
+            }
             // TODO: this seems like a hack to fix?
             None => magnus::eval::<RHash>(r#"Selma::Sanitizer::Config::DEFAULT"#).unwrap(),
         };
 
+        Ok(Self::with_config(Some(config)))
+    }
+
+    /// `Selma::Sanitizer.new`, which applies the config right away, so that
+    /// `allow_*` and `disallow_*` calls made afterwards aren't undone by it.
+    fn rb_new(arguments: &[Value]) -> Result<Value, magnus::Error> {
+        let sanitizer = WrappedStruct::from(Self::new(arguments)?);
+        sanitizer.funcall::<&str, (), Value>("setup", ())?;
+
+        Ok(sanitizer.into())
+    }
+
+    fn with_config(config: Option<RHash>) -> Self {
         let mut element_sanitizers = HashMap::new();
         crate::tags::Tag::html_tags().iter().for_each(|html_tag| {
             let es = ElementSanitizer::default();
@@ -68,52 +336,202 @@ impl SelmaSanitizer {
             );
         });
 
-        Ok(Self(std::cell::RefCell::new(Sanitizer {
-            flags: [0; crate::tags::Tag::TAG_COUNT],
+        Self(std::cell::RefCell::new(Sanitizer {
+            flags: vec![0; crate::tags::Tag::TAG_COUNT],
+            custom_elements: vec![],
             allowed_attrs: vec![],
-            allowed_classes: vec![],
+            allowed_classes: ClassAllowlist::default(),
             element_sanitizers,
+            css: None,
+            links: None,
+            head: None,
+            id_prefix: None,
+            base_url: None,
+            remove_whitespace_only_elements: false,
+            svg: None,
+            math: None,
 
             escape_tagfilter: true,
+            gfm_tagfilter: false,
             allow_comments: false,
+            comment_pattern: None,
+            comment_pattern_source: None,
             allow_doctype: true,
+            doctype_policy: DoctypePolicy::Keep,
+            removed_contents: Cell::new(false),
             config,
-        })))
+            configured: false,
+        }))
+    }
+
+    /// @yard
+    /// @def from_json(json)
+    /// @param json [String] A policy document, shaped like a sanitizer config
+    /// @return [Selma::Sanitizer]
+    fn from_json(json: String) -> Result<Self, magnus::Error> {
+        let policy: Policy = serde_json::from_str(&json)
+            .map_err(|err| policy::config_error(format!("invalid JSON policy: {err}")))?;
+
+        Self::from_policy(&policy)
     }
 
-    fn get_config(&self) -> Result<RHash, magnus::Error> {
+    fn from_policy(policy: &Policy) -> Result<Self, magnus::Error> {
+        let sanitizer = Self::with_config(None);
+        policy.apply(&sanitizer)?;
+        sanitizer.0.borrow_mut().configured = true;
+
+        Ok(sanitizer)
+    }
+
+    /// The effective policy, as a JSON document `from_json` accepts.
+    fn to_policy_json(&self) -> Result<String, magnus::Error> {
+        serde_json::to_string_pretty(&Policy::from(&*self.0.borrow()))
+            .map_err(|err| magnus::Error::new(exception::runtime_error(), err.to_string()))
+    }
+
+    /// Runs just the sanitization passes over the fragment `html`, without a
+    /// `Selma::Rewriter` or its handlers.
+    fn sanitize_html(&self, html: String) -> Result<String, magnus::Error> {
+        self.sanitize_in_mode(&html, SanitizeMode::Fragment)
+    }
+
+    /// Like `sanitize_html`, for whole documents.
+    fn sanitize_document_html(&self, html: String) -> Result<String, magnus::Error> {
+        self.sanitize_in_mode(&html, SanitizeMode::Document)
+    }
+
+    fn sanitize_in_mode(&self, html: &str, mode: SanitizeMode) -> Result<String, magnus::Error> {
+        let sanitized_html = SelmaRewriter::perform_sanitization(self, html, Some(mode))?;
+
+        String::from_utf8(sanitized_html)
+            .map_err(|err| magnus::Error::new(exception::runtime_error(), err.to_string()))
+    }
+
+    fn get_configured(&self) -> bool {
+        self.0.borrow().configured
+    }
+
+    fn set_configured(&self, configured: bool) -> bool {
+        self.0.borrow_mut().configured = configured;
+        configured
+    }
+
+    /// Whether `element_name` is an element the sanitizer knows about.
+    fn is_known_element(element_name: String) -> bool {
+        crate::tags::Tag::tag_from_tag_name(element_name.as_str()).index
+            != crate::tags::HTMLTag::UNKNOWN as usize
+    }
+
+    /// Whether `element_name` can be given in a config or policy.
+    fn is_valid_element_name(element_name: String) -> bool {
+        policy::is_element_name(&element_name)
+    }
+
+    /// Why `pattern` can't be used as a whole-value pattern, if it can't.
+    fn pattern_error(pattern: String) -> Option<String> {
+        RegexBuilder::new(&format!(r"\A(?:{pattern})\z"))
+            .build()
+            .err()
+            .map(|err| err.to_string())
+    }
+
+    fn get_config(&self) -> Result<Option<RHash>, magnus::Error> {
         let binding = self.0.borrow();
 
         Ok(binding.config)
     }
 
     /// Toggle a sanitizer option on or off.
-    fn set_flag(&self, tag_name: String, flag: u8, set: bool) {
-        let tag = crate::tags::Tag::tag_from_tag_name(tag_name.as_str());
+    fn set_flag(&self, tag_name: String, flag: u8, set: bool) -> Result<(), magnus::Error> {
+        let mut binding = self.0.borrow_mut();
+        let index = binding.flags_index_mut(tag_name.as_str())?;
         if set {
-            self.0.borrow_mut().flags[tag.index] |= flag;
+            binding.flags[index] |= flag;
         } else {
-            self.0.borrow_mut().flags[tag.index] &= !flag;
+            binding.flags[index] &= !flag;
         }
+
+        Ok(())
     }
 
     /// Toggles all sanitization options on or off.
     fn set_all_flags(&self, flag: u8, set: bool) {
-        if set {
-            crate::tags::Tag::html_tags()
-                .iter()
-                .enumerate()
-                .for_each(|(iter, _)| {
-                    self.0.borrow_mut().flags[iter] |= flag;
-                });
-        } else {
-            crate::tags::Tag::html_tags()
-                .iter()
-                .enumerate()
-                .for_each(|(iter, _)| {
-                    self.0.borrow_mut().flags[iter] &= flag;
-                });
+        let mut binding = self.0.borrow_mut();
+
+        for flags in binding.flags.iter_mut() {
+            if set {
+                *flags |= flag;
+            } else {
+                *flags &= !flag;
+            }
+        }
+    }
+
+    /// Names of the elements that are allowed.
+    fn get_allowed_elements(&self) -> Vec<String> {
+        self.0.borrow().elements_with(Self::SELMA_SANITIZER_ALLOW)
+    }
+
+    /// Attributes allowed on `element_name`, including those allowed on all
+    /// elements, or just the latter for `"all"`.
+    fn get_allowed_attributes(&self, element_name: String) -> Vec<String> {
+        let binding = self.0.borrow();
+        let mut allowed_attrs = vec![];
+
+        if element_name != "all" {
+            if let Some(element_sanitizer) = binding.element_sanitizers.get(&element_name) {
+                allowed_attrs.extend(element_sanitizer.allowed_attrs.iter().cloned());
+            }
         }
+        for attr_name in &binding.allowed_attrs {
+            if !allowed_attrs.contains(attr_name) {
+                allowed_attrs.push(attr_name.clone());
+            }
+        }
+
+        allowed_attrs
+    }
+
+    /// Protocols allowed in `attr_name` on `element_name`, with `":relative"` and
+    /// `":protocol_relative"` standing in for relative URLs.
+    fn get_allowed_protocols(&self, element_name: String, attr_name: String) -> Vec<String> {
+        let binding = self.0.borrow();
+
+        binding
+            .element_sanitizers
+            .get(&element_name)
+            .and_then(|element_sanitizer| element_sanitizer.protocol_sanitizers.get(&attr_name))
+            .map(|entries| url::protocol_names(entries))
+            .unwrap_or_default()
+    }
+
+    /// Whether elements holding only whitespace count as empty.
+    fn set_remove_whitespace_only_elements(&self, remove: bool) -> bool {
+        self.0.borrow_mut().remove_whitespace_only_elements = remove;
+        remove
+    }
+
+    pub fn get_remove_whitespace_only_elements(&self) -> bool {
+        self.0.borrow().remove_whitespace_only_elements
+    }
+
+    /// Whether any element is removed when it's left empty.
+    pub fn removes_empty_elements(&self) -> bool {
+        self.0
+            .borrow()
+            .flags
+            .iter()
+            .any(|flags| flags & Self::SELMA_SANITIZER_REMOVE_IF_EMPTY != 0)
+    }
+
+    pub fn should_remove_if_empty(&self, element: &mut Element) -> bool {
+        self.0.borrow().flags_for(&element.tag_name()) & Self::SELMA_SANITIZER_REMOVE_IF_EMPTY != 0
+    }
+
+    /// Whether `element`, if it's disallowed, is written out as text rather
+    /// than removed.
+    pub fn should_escape(&self, element: &Element) -> bool {
+        self.0.borrow().flags_for(&element.tag_name()) & Self::SELMA_SANITIZER_ESCAPE != 0
     }
 
     /// Whether or not to keep dangerous HTML tags.
@@ -122,22 +540,41 @@ impl SelmaSanitizer {
         allow
     }
 
-    pub fn escape_tagfilter(&self, e: &mut Element) -> bool {
-        if self.0.borrow().escape_tagfilter {
-            let tag = crate::tags::Tag::tag_from_element(e);
-            if crate::tags::Tag::is_tag_escapeworthy(tag) {
-                e.remove();
-                return true;
-            }
+    /// Removes disallowed elements that `Tag::is_tag_escapeworthy` lists,
+    /// along with their contents, unless they're escaped. Returns whether
+    /// `element` was removed.
+    pub fn escape_tagfilter(&self, element: &mut Element) -> bool {
+        if !self.0.borrow().escape_tagfilter
+            || !self.allow_element(element)
+            || self.should_escape(element)
+        {
+            return false;
+        }
+
+        let tag = crate::tags::Tag::tag_from_element(element);
+        if !crate::tags::Tag::is_tag_escapeworthy(tag) {
+            return false;
         }
 
-        false
+        self.force_remove_element(element);
+        true
     }
 
     pub fn get_escape_tagfilter(&self) -> bool {
         self.0.borrow().escape_tagfilter
     }
 
+    /// Whether or not to escape filtered tags like GitHub Flavored Markdown's
+    /// tagfilter, by replacing their leading `<` with `&lt;`.
+    fn set_gfm_tagfilter(&self, gfm: bool) -> bool {
+        self.0.borrow_mut().gfm_tagfilter = gfm;
+        gfm
+    }
+
+    pub fn get_gfm_tagfilter(&self) -> bool {
+        self.0.borrow().gfm_tagfilter
+    }
+
     /// Whether or not to keep HTML comments.
     fn set_allow_comments(&self, allow: bool) -> bool {
         self.0.borrow_mut().allow_comments = allow;
@@ -148,8 +585,68 @@ impl SelmaSanitizer {
         self.0.borrow().allow_comments
     }
 
-    pub fn remove_comment(&self, c: &mut Comment) {
-        c.remove();
+    /// Only keeps comments whose text, less surrounding whitespace, all
+    /// matches `pattern`, or every comment for `None`.
+    fn set_comment_pattern(
+        &self,
+        pattern: Option<String>,
+        case_insensitive: bool,
+    ) -> Result<(), magnus::Error> {
+        let regex = match &pattern {
+            Some(pattern) => Some(
+                RegexBuilder::new(&format!(r"\A(?:{pattern})\z"))
+                    .case_insensitive(case_insensitive)
+                    .build()
+                    .map_err(|err| {
+                        magnus::Error::new(
+                            exception::arg_error(),
+                            format!("invalid comment pattern: {err}"),
+                        )
+                    })?,
+            ),
+            None => None,
+        };
+
+        let mut binding = self.0.borrow_mut();
+        binding.comment_pattern = regex;
+        binding.comment_pattern_source = pattern.map(|pattern| (pattern, case_insensitive));
+
+        Ok(())
+    }
+
+    /// Removes comments, unless they're allowed. Even then, IE conditional
+    /// comments, and comments with a nested `<!--`, are removed. The rest are
+    /// kept as they were written.
+    pub fn sanitize_comment(&self, c: &mut Comment) {
+        let binding = self.0.borrow();
+        let text = c.text();
+
+        let keep = binding.allow_comments
+            && !Self::is_dangerous_comment(&text)
+            && match &binding.comment_pattern {
+                Some(pattern) => pattern.is_match(text.trim()),
+                None => true,
+            };
+
+        if !keep {
+            c.remove();
+        }
+    }
+
+    /// Comments end at the first `-->` or `--!>`, so neither can be in `text`.
+    fn is_dangerous_comment(text: &str) -> bool {
+        // `<!--[if IE]>`, `<![endif]-->`, and their downlevel-revealed
+        // `<![if !IE]>` and `<![endif]>` forms
+        let directive = text.trim_start();
+        let directive = directive.strip_prefix("<!").unwrap_or(directive);
+        let is_conditional = directive
+            .get(..3)
+            .is_some_and(|start| start.eq_ignore_ascii_case("[if"))
+            || directive
+                .get(..6)
+                .is_some_and(|start| start.eq_ignore_ascii_case("[endif"));
+
+        is_conditional || text.contains("<!--")
     }
 
     /// Whether or not to keep HTML doctype.
@@ -163,85 +660,546 @@ impl SelmaSanitizer {
         self.0.borrow().allow_doctype
     }
 
-    pub fn remove_doctype(&self, d: &mut Doctype) {
-        d.remove();
+    /// `:normalize` writes every doctype as `<!DOCTYPE html>`, and
+    /// `:reject_legacy` drops those with public or system identifiers.
+    fn set_doctype_policy(&self, policy: Symbol) -> Result<(), magnus::Error> {
+        let policy = match policy.name()?.as_ref() {
+            "keep" => DoctypePolicy::Keep,
+            "normalize" => DoctypePolicy::Normalize,
+            "reject_legacy" => DoctypePolicy::RejectLegacy,
+            other => {
+                return Err(magnus::Error::new(
+                    exception::arg_error(),
+                    format!("unknown doctype policy `:{other}`; expected `:keep`, `:normalize`, or `:reject_legacy`"),
+                ));
+            }
+        };
+
+        self.0.borrow_mut().doctype_policy = policy;
+
+        Ok(())
+    }
+
+    /// Whether doctypes are sanitized at all, rather than kept as they are.
+    pub fn sanitizes_doctypes(&self) -> bool {
+        let binding = self.0.borrow();
+
+        !binding.allow_doctype || binding.doctype_policy != DoctypePolicy::Keep
+    }
+
+    /// Removes doctypes that aren't allowed, returning whether a normalized
+    /// `<!DOCTYPE html>` should be written in this one's place. Doctypes can't
+    /// be rewritten, so that's left to the caller.
+    pub fn sanitize_doctype(&self, d: &mut Doctype) -> bool {
+        let binding = self.0.borrow();
+
+        let keep = binding.allow_doctype
+            && (binding.doctype_policy != DoctypePolicy::RejectLegacy
+                || !Self::is_legacy_doctype(d));
+        let normalize = keep && binding.doctype_policy == DoctypePolicy::Normalize;
+
+        if !keep || normalize {
+            d.remove();
+        }
+
+        normalize
+    }
+
+    /// `<!DOCTYPE html SYSTEM "about:legacy-compat">` is still an HTML5 doctype.
+    fn is_legacy_doctype(d: &Doctype) -> bool {
+        !d.name()
+            .is_some_and(|name| name.eq_ignore_ascii_case("html"))
+            || d.public_id().is_some()
+            || d.system_id()
+                .is_some_and(|system_id| system_id != "about:legacy-compat")
+    }
+
+    fn set_allowed_attribute(&self, eln: Value, attr_name: String, allow: bool) -> bool {
+        let element_name = eln.to_r_string().unwrap().to_string().unwrap();
+
+        self.allow_attribute(&element_name, attr_name, allow)
+    }
+
+    fn allow_attribute(&self, element_name: &str, attr_name: String, allow: bool) -> bool {
+        let mut binding = self.0.borrow_mut();
+
+        if element_name == "all" {
+            let allowed_attrs = &mut binding.allowed_attrs;
+            Self::set_allowed(allowed_attrs, &attr_name, allow);
+        } else {
+            let element_sanitizers = &mut binding.element_sanitizers;
+            let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, element_name);
+
+            Self::set_allowed(&mut element_sanitizer.allowed_attrs, &attr_name, allow);
+        }
+
+        allow
+    }
+
+    fn set_required_attribute(
+        &self,
+        element_name: String,
+        attr_name: String,
+        require: bool,
+    ) -> bool {
+        let mut binding = self.0.borrow_mut();
+
+        let element_sanitizers = &mut binding.element_sanitizers;
+        let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, &element_name);
+
+        let required_attrs = element_sanitizer.required_attrs.borrow_mut();
+        Self::set_allowed(required_attrs, &attr_name, require);
+
+        require
+    }
+
+    /// What to do with `element_name` when it has none of its required attributes:
+    /// `:remove` drops it with its contents, `:unwrap` keeps the contents.
+    fn set_required_attribute_action(
+        &self,
+        element_name: String,
+        action: Symbol,
+    ) -> Result<(), magnus::Error> {
+        let action = match action.name()?.as_ref() {
+            "remove" => RequiredAttributeAction::Remove,
+            "unwrap" => RequiredAttributeAction::Unwrap,
+            other => {
+                return Err(magnus::Error::new(
+                    exception::arg_error(),
+                    format!("unknown required attribute action `:{other}`; expected `:remove` or `:unwrap`"),
+                ));
+            }
+        };
+
+        self.set_required_attrs_action(&element_name, action);
+
+        Ok(())
+    }
+
+    fn set_required_attrs_action(&self, element_name: &str, action: RequiredAttributeAction) {
+        let mut binding = self.0.borrow_mut();
+        let element_sanitizers = &mut binding.element_sanitizers;
+        let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, element_name);
+        element_sanitizer.required_attrs_action = action;
+    }
+
+    fn set_allowed_class(
+        &self,
+        element_name: String,
+        class_name: String,
+        allow: bool,
+    ) -> Result<bool, magnus::Error> {
+        self.with_class_allowlist(&element_name, |allowed_classes| {
+            allowed_classes.set_allowed(&class_name, allow)
+        })?;
+
+        Ok(allow)
+    }
+
+    /// Allows classes on `element_name` that match all of `pattern`.
+    fn set_allowed_class_pattern(
+        &self,
+        element_name: String,
+        pattern: String,
+        case_insensitive: bool,
+        allow: bool,
+    ) -> Result<bool, magnus::Error> {
+        self.with_class_allowlist(&element_name, |allowed_classes| {
+            allowed_classes.set_allowed_pattern(&pattern, case_insensitive, allow)
+        })?;
+
+        Ok(allow)
+    }
+
+    /// Allows `element_name` inside `<svg>` or `<math>`, per `namespace`.
+    fn set_allowed_foreign_element(
+        &self,
+        namespace: String,
+        element_name: String,
+        allow: bool,
+    ) -> Result<bool, magnus::Error> {
+        self.with_foreign_sanitizer(&namespace, |foreign_sanitizer| {
+            foreign_sanitizer.set_allowed_element(&element_name, allow)
+        })?;
+
+        Ok(allow)
+    }
+
+    /// Allows `attr_name` on `element_name`, or on every element for `"all"`,
+    /// inside `<svg>` or `<math>`, per `namespace`.
+    fn set_allowed_foreign_attribute(
+        &self,
+        namespace: String,
+        element_name: String,
+        attr_name: String,
+        allow: bool,
+    ) -> Result<bool, magnus::Error> {
+        self.with_foreign_sanitizer(&namespace, |foreign_sanitizer| {
+            foreign_sanitizer.set_allowed_attribute(&element_name, &attr_name, allow)
+        })?;
+
+        Ok(allow)
+    }
+
+    fn with_foreign_sanitizer(
+        &self,
+        namespace: &str,
+        update: impl FnOnce(&mut ForeignSanitizer),
+    ) -> Result<(), magnus::Error> {
+        let mut binding = self.0.borrow_mut();
+        let foreign_sanitizer = match Namespace::from_key(namespace) {
+            Some(Namespace::Svg) => &mut binding.svg,
+            Some(Namespace::MathMl) => &mut binding.math,
+            _ => {
+                return Err(magnus::Error::new(
+                    exception::arg_error(),
+                    format!("unknown foreign namespace `{namespace}`; expected `svg` or `math`"),
+                ))
+            }
+        };
+
+        update(foreign_sanitizer.get_or_insert_with(Default::default));
+        Ok(())
+    }
+
+    pub fn sanitizes_foreign_content(&self) -> bool {
+        let binding = self.0.borrow();
+        binding.svg.is_some() || binding.math.is_some()
+    }
+
+    fn with_class_allowlist(
+        &self,
+        element_name: &str,
+        update: impl FnOnce(&mut ClassAllowlist) -> Result<(), regex::Error>,
+    ) -> Result<(), magnus::Error> {
+        let mut binding = self.0.borrow_mut();
+        let allowed_classes = if element_name == "all" {
+            &mut binding.allowed_classes
+        } else {
+            let element_sanitizers = &mut binding.element_sanitizers;
+            let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, element_name);
+
+            &mut element_sanitizer.allowed_classes
+        };
+
+        update(allowed_classes).map_err(|err| {
+            magnus::Error::new(
+                exception::arg_error(),
+                format!("invalid class pattern for `{element_name}`: {err}"),
+            )
+        })
+    }
+
+    fn set_allowed_protocols(
+        &self,
+        element_name: String,
+        attr_name: String,
+        allow_list: RArray,
+        allow: bool,
+    ) -> Result<(), magnus::Error> {
+        let mut entries = vec![];
+        for opt_allowed_protocol in allow_list.each() {
+            let allowed_protocol = opt_allowed_protocol.unwrap();
+            entries.extend(Self::protocol_list_entries(allowed_protocol)?);
+        }
+
+        self.set_protocol_list_entries(element_name, attr_name, entries, allow);
+
+        Ok(())
+    }
+
+    fn set_protocol_list_entries(
+        &self,
+        element_name: String,
+        attr_name: String,
+        entries: Vec<String>,
+        allow: bool,
+    ) {
+        let mut binding = self.0.borrow_mut();
+
+        let element_sanitizers = &mut binding.element_sanitizers;
+        let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, &element_name);
+
+        let protocol_sanitizers = &mut element_sanitizer.protocol_sanitizers.borrow_mut();
+
+        let protocol_list = protocol_sanitizers.entry(attr_name).or_default();
+        for entry in &entries {
+            Self::set_allowed(protocol_list, entry, allow);
+        }
+    }
+
+    /// Protocols are matched case-insensitively, and the `:relative` and
+    /// `:protocol_relative` symbols are stored as markers for those URL kinds.
+    fn protocol_list_entries(allowed_protocol: Value) -> Result<Vec<String>, magnus::Error> {
+        if allowed_protocol.is_kind_of(class::string()) {
+            return Ok(vec![allowed_protocol.to_string().to_ascii_lowercase()]);
+        }
+
+        let entries = if allowed_protocol.is_kind_of(class::symbol()) {
+            url::relative_protocol_entries(allowed_protocol.inspect().as_str())
+        } else {
+            None
+        };
+
+        entries.ok_or_else(|| {
+            magnus::Error::new(
+                exception::arg_error(),
+                format!(
+                    "unknown protocol `{}`; expected a String, `:relative`, or `:protocol_relative`",
+                    allowed_protocol.inspect()
+                ),
+            )
+        })
+    }
+
+    /// Only keeps `attr_name` on `element_name` if its value is one of `values`.
+    fn set_allowed_attribute_values(
+        &self,
+        element_name: String,
+        attr_name: String,
+        values: Vec<String>,
+    ) {
+        let mut binding = self.0.borrow_mut();
+        let element_sanitizers = &mut binding.element_sanitizers;
+        let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, &element_name);
+
+        let constraint = element_sanitizer
+            .value_constraints
+            .entry(attr_name)
+            .or_default();
+        constraint.values = Some(values);
+    }
+
+    /// Only keeps `attr_name` on `element_name` if all of its value matches `pattern`.
+    fn set_attribute_value_pattern(
+        &self,
+        element_name: String,
+        attr_name: String,
+        pattern: String,
+        case_insensitive: bool,
+    ) -> Result<(), magnus::Error> {
+        // the pattern has to match the entire value
+        let regex = RegexBuilder::new(&format!(r"\A(?:{pattern})\z"))
+            .case_insensitive(case_insensitive)
+            .build()
+            .map_err(|err| {
+                magnus::Error::new(
+                    exception::arg_error(),
+                    format!("invalid pattern for `{element_name}[{attr_name}]`: {err}"),
+                )
+            })?;
+
+        let mut binding = self.0.borrow_mut();
+        let element_sanitizers = &mut binding.element_sanitizers;
+        let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, &element_name);
+
+        let constraint = element_sanitizer
+            .value_constraints
+            .entry(attr_name)
+            .or_default();
+        constraint.pattern = Some(regex);
+        constraint.pattern_source = Some((pattern, case_insensitive));
+
+        Ok(())
+    }
+
+    /// Only keeps `attr_name` on `element_name` if its value has at most `max_length` characters.
+    fn set_attribute_value_max_length(
+        &self,
+        element_name: String,
+        attr_name: String,
+        max_length: usize,
+    ) {
+        let mut binding = self.0.borrow_mut();
+        let element_sanitizers = &mut binding.element_sanitizers;
+        let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, &element_name);
+
+        let constraint = element_sanitizer
+            .value_constraints
+            .entry(attr_name)
+            .or_default();
+        constraint.max_length = Some(max_length);
+    }
+
+    /// Only keeps `data:` URLs in `attr_name` on `element_name` if they're
+    /// base64-encoded, have one of `mime_types`, and decode to no more than
+    /// `max_size` bytes.
+    fn set_data_uri_policy(
+        &self,
+        element_name: String,
+        attr_name: String,
+        mime_types: Vec<String>,
+        max_size: Option<usize>,
+    ) {
+        let mut binding = self.0.borrow_mut();
+        let element_sanitizers = &mut binding.element_sanitizers;
+        let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, &element_name);
+
+        element_sanitizer
+            .data_uri_policies
+            .insert(attr_name, DataUriPolicy::new(&mime_types, max_size));
+    }
+
+    fn set_allowed_css_property(&self, property: String, allow: bool) -> bool {
+        let mut binding = self.0.borrow_mut();
+        let css = binding.css.get_or_insert_with(CssSanitizer::default);
+
+        css.set_allowed_property(&property, allow);
+
+        allow
+    }
+
+    /// Configuring CSS protocols, even with an empty list, turns on
+    /// property filtering for `style` attributes.
+    fn set_allowed_css_protocols(&self, allow_list: RArray) -> Result<(), magnus::Error> {
+        let mut entries = vec![];
+        for opt_allowed_protocol in allow_list.each() {
+            let allowed_protocol = opt_allowed_protocol.unwrap();
+            entries.extend(Self::protocol_list_entries(allowed_protocol)?);
+        }
+
+        self.add_allowed_css_protocols(entries);
+
+        Ok(())
+    }
+
+    fn add_allowed_css_protocols(&self, entries: Vec<String>) {
+        let mut binding = self.0.borrow_mut();
+        let css = binding.css.get_or_insert_with(CssSanitizer::default);
+
+        for protocol in entries {
+            css.set_allowed_protocol(&protocol);
+        }
+    }
+
+    /// `rel` tokens to add to every external link, or to replace their own with.
+    fn set_link_rel(&self, tokens: Vec<String>, mode: Symbol) -> Result<(), magnus::Error> {
+        let mode = match mode.name()?.as_ref() {
+            "merge" => RelMode::Merge,
+            "force" => RelMode::Force,
+            other => {
+                return Err(magnus::Error::new(
+                    exception::arg_error(),
+                    format!("unknown rel mode `:{other}`; expected `:merge` or `:force`"),
+                ));
+            }
+        };
+
+        let mut binding = self.0.borrow_mut();
+        let links = binding.links.get_or_insert_with(LinkSanitizer::default);
+
+        links.set_rel(&tokens, mode);
+
+        Ok(())
+    }
+
+    fn set_allowed_link_targets(&self, targets: Vec<String>) {
+        let mut binding = self.0.borrow_mut();
+        let links = binding.links.get_or_insert_with(LinkSanitizer::default);
+
+        links.set_allowed_targets(&targets);
+    }
+
+    fn set_internal_link_hosts(&self, hosts: Vec<String>) {
+        let mut binding = self.0.borrow_mut();
+        let links = binding.links.get_or_insert_with(LinkSanitizer::default);
+
+        links.set_internal_hosts(&hosts);
+    }
+
+    /// Hosts `<base href>` may point to.
+    fn set_base_hosts(&self, hosts: Vec<String>) {
+        let mut binding = self.0.borrow_mut();
+        let head = binding.head.get_or_insert_with(HeadSanitizer::default);
+
+        head.set_base_hosts(&hosts);
     }
 
-    fn set_allowed_attribute(&self, eln: Value, attr_name: String, allow: bool) -> bool {
+    fn set_allowed_link_rels(&self, rels: Vec<String>) {
         let mut binding = self.0.borrow_mut();
+        let head = binding.head.get_or_insert_with(HeadSanitizer::default);
 
-        let element_name = eln.to_r_string().unwrap().to_string().unwrap();
-        if element_name == "all" {
-            let allowed_attrs = &mut binding.allowed_attrs;
-            Self::set_allowed(allowed_attrs, &attr_name, allow);
-        } else {
-            let element_sanitizers = &mut binding.element_sanitizers;
-            let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, &element_name);
+        head.set_allowed_link_rels(&rels);
+    }
 
-            element_sanitizer.allowed_attrs.push(attr_name);
-        }
+    /// Hosts `<link rel="stylesheet">` may load from.
+    fn set_stylesheet_hosts(&self, hosts: Vec<String>) {
+        let mut binding = self.0.borrow_mut();
+        let head = binding.head.get_or_insert_with(HeadSanitizer::default);
 
-        allow
+        head.set_stylesheet_hosts(&hosts);
     }
 
-    fn set_allowed_class(&self, element_name: String, class_name: String, allow: bool) -> bool {
-        let mut binding = self.0.borrow_mut();
-        if element_name == "all" {
-            let allowed_classes = &mut binding.allowed_classes;
-            Self::set_allowed(allowed_classes, &class_name, allow);
-        } else {
-            let element_sanitizers = &mut binding.element_sanitizers;
-            let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, &element_name);
+    /// Prefix for `id` and `name` values, and for `href="#..."` links.
+    fn set_id_prefix(&self, id_prefix: Option<String>) {
+        self.0.borrow_mut().id_prefix = id_prefix.filter(|prefix| !prefix.is_empty());
+    }
 
-            let allowed_classes = element_sanitizer.allowed_classes.borrow_mut();
-            Self::set_allowed(allowed_classes, &class_name, allow)
-        }
-        allow
+    /// Resolves relative URLs against `base_url`, or just those starting with
+    /// `/`, if `root_relative_only` is set.
+    fn set_base_url(
+        &self,
+        base_url: Option<String>,
+        root_relative_only: bool,
+    ) -> Result<(), magnus::Error> {
+        let base_url = match base_url {
+            None => None,
+            Some(base_url) => Some(BaseUrl::parse(&base_url, root_relative_only).ok_or_else(
+                || {
+                    magnus::Error::new(
+                        exception::arg_error(),
+                        format!(
+                            "invalid base URL `{base_url}`; expected an absolute http or https URL"
+                        ),
+                    )
+                },
+            )?),
+        };
+
+        self.0.borrow_mut().base_url = base_url;
+
+        Ok(())
     }
 
-    fn set_allowed_protocols(&self, element_name: String, attr_name: String, allow_list: RArray) {
+    /// Nests every rule in sanitized `<style>` elements under this selector.
+    fn set_css_scope(&self, scope: Option<String>) -> Result<(), magnus::Error> {
+        if let Some(scope) = &scope {
+            if scope.contains(['{', '}', ';', '<', '@']) {
+                return Err(magnus::Error::new(
+                    exception::arg_error(),
+                    format!("invalid CSS scope `{scope}`; expected a selector"),
+                ));
+            }
+        }
+
         let mut binding = self.0.borrow_mut();
+        let css = binding.css.get_or_insert_with(CssSanitizer::default);
 
-        let element_sanitizers = &mut binding.element_sanitizers;
-        let element_sanitizer = Self::get_element_sanitizer(element_sanitizers, &element_name);
+        css.set_scope(scope);
 
-        let protocol_sanitizers = &mut element_sanitizer.protocol_sanitizers.borrow_mut();
+        Ok(())
+    }
 
-        for opt_allowed_protocol in allow_list.each() {
-            let allowed_protocol = opt_allowed_protocol.unwrap();
-            let protocol_list = protocol_sanitizers.get_mut(&attr_name);
-            if allowed_protocol.is_kind_of(class::string()) {
-                match protocol_list {
-                    None => {
-                        protocol_sanitizers
-                            .insert(attr_name.to_string(), vec![allowed_protocol.to_string()]);
-                    }
-                    Some(protocol_list) => protocol_list.push(allowed_protocol.to_string()),
-                }
-            } else if allowed_protocol.is_kind_of(class::symbol())
-                && allowed_protocol.inspect() == ":relative"
-            {
-                match protocol_list {
-                    None => {
-                        protocol_sanitizers.insert(
-                            attr_name.to_string(),
-                            vec!["#".to_string(), "/".to_string()],
-                        );
-                    }
-                    Some(protocol_list) => {
-                        protocol_list.push("#".to_string());
-                        protocol_list.push("/".to_string());
-                    }
-                }
-            }
+    /// `<style>` contents are only kept, and sanitized, when CSS has been
+    /// configured and `<style>` itself is allowed.
+    pub fn sanitizes_style_elements(&self) -> bool {
+        let binding = self.0.borrow();
+
+        binding.css.is_some() && binding.flags_for("style") & Self::SELMA_SANITIZER_ALLOW != 0
+    }
+
+    pub fn sanitize_stylesheet(&self, css: &str) -> String {
+        match &self.0.borrow().css {
+            Some(css_sanitizer) => css_sanitizer.sanitize_stylesheet(css),
+            None => String::new(),
         }
     }
 
     fn set_allowed(set: &mut Vec<String>, attr_name: &String, allow: bool) {
-        if allow {
+        if !allow {
+            set.retain(|x| x != attr_name);
+        } else if !set.contains(attr_name) {
             set.push(attr_name.to_string());
-        } else if set.contains(attr_name) {
-            set.swap_remove(set.iter().position(|x| x == attr_name).unwrap());
         }
     }
 
@@ -250,8 +1208,9 @@ impl SelmaSanitizer {
         let tag_name = &element.tag_name();
         let element_sanitizer = {
             let mut binding = self.0.borrow_mut();
+            let element_sanitizer_name = binding.element_sanitizer_name(tag_name).to_string();
             let element_sanitizers = &mut binding.element_sanitizers;
-            Self::get_element_sanitizer(element_sanitizers, tag_name).clone()
+            Self::get_element_sanitizer(element_sanitizers, &element_sanitizer_name).clone()
         };
 
         let binding = self.0.borrow();
@@ -268,21 +1227,20 @@ impl SelmaSanitizer {
             // an HTML tag to pass malicious data. If this is
             // encountered, remove the entire element to be safe.
             if attr_name.starts_with("<!--") {
-                Self::force_remove_element(self, element);
+                self.force_remove_element(element);
                 return Ok(());
             }
 
             // first, trim leading spaces and unescape any encodings
             let trimmed = attr_val.trim_start();
             let x = escapist::unescape_html(trimmed.as_bytes());
-            let unescaped_attr_val = String::from_utf8_lossy(&x).to_string();
+            let mut unescaped_attr_val = String::from_utf8_lossy(&x).to_string();
 
             let should_keep_attrubute = match Self::should_keep_attribute(
                 &binding,
-                element,
                 &element_sanitizer,
                 attr_name,
-                &unescaped_attr_val,
+                &mut unescaped_attr_val,
             ) {
                 Ok(should_keep) => should_keep,
                 Err(e) => {
@@ -304,49 +1262,185 @@ impl SelmaSanitizer {
                             }
                         }
                     }
-                } else if !unescaped_attr_val.is_empty() {
-                    let mut buf = String::new();
-                    // ...then, escape any special characters, for security
-                    if attr_name == "href" {
-                        escapist::escape_href(&mut buf, unescaped_attr_val.as_str());
-                    } else {
-                        escapist::escape_html(&mut buf, unescaped_attr_val.as_str());
-                    };
-
-                    match element.set_attribute(attr_name, &buf) {
-                        Ok(_) => {}
-                        Err(err) => {
-                            return Err(err);
-                        }
-                    }
+                } else {
+                    Self::write_attribute(&binding, element, attr_name, unescaped_attr_val)?;
                 }
             }
         }
 
+        if binding
+            .head
+            .as_ref()
+            .is_some_and(|head| head.should_remove(element))
+        {
+            self.force_remove_element(element);
+            return Ok(());
+        }
+
+        if let Some(links) = &binding.links {
+            links.sanitize_link(element)?;
+        }
+
         let required = &element_sanitizer.required_attrs;
-        if required.contains(&"*".to_string()) {
+        if required.is_empty() {
             return Ok(());
         }
-        for attr in element.attributes().iter() {
-            let attr_name = &attr.name();
-            if required.contains(attr_name) {
-                return Ok(());
-            }
+
+        // `*` means any attribute at all will do
+        let any_attr = required.contains(&"*".to_string());
+        let has_required_attr = element
+            .attributes()
+            .iter()
+            .any(|attr| any_attr || required.contains(&attr.name()));
+
+        if !has_required_attr {
+            let flags = match element_sanitizer.required_attrs_action {
+                RequiredAttributeAction::Remove => Self::SELMA_SANITIZER_REMOVE_CONTENTS,
+                RequiredAttributeAction::Unwrap => {
+                    binding.flags_for(tag_name) & !Self::SELMA_SANITIZER_REMOVE_CONTENTS
+                }
+            };
+            self.remove_element(element, tag.self_closing, flags);
+            Self::check_if_end_tag_needs_removal(element);
         }
 
         Ok(())
     }
 
-    fn should_keep_attribute(
+    /// Writes back a kept attribute's unescaped value, escaped again.
+    fn write_attribute(
         binding: &Sanitizer,
         element: &mut Element,
+        attr_name: &str,
+        mut unescaped_attr_val: String,
+    ) -> Result<(), AttributeNameError> {
+        if unescaped_attr_val.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(id_prefix) = &binding.id_prefix {
+            Self::prefix_id_reference(id_prefix, attr_name, &mut unescaped_attr_val);
+        }
+
+        let mut buf = String::new();
+        // ...then, escape any special characters, for security
+        if attr_name == "href" {
+            escapist::escape_href(&mut buf, unescaped_attr_val.as_str());
+        } else {
+            escapist::escape_html(&mut buf, unescaped_attr_val.as_str());
+        };
+
+        element.set_attribute(attr_name, &buf)
+    }
+
+    /// Sanitizes an element inside `<svg>` or `<math>`, returning whether it
+    /// was dealt with. HTML elements there are left to the usual rules, as
+    /// are foreign elements without an allowlist for their namespace, once
+    /// anything that reparses differently has been removed.
+    pub fn sanitize_foreign_element(
+        &self,
+        element: &mut Element,
+        placement: &Placement,
+    ) -> Result<bool, AttributeNameError> {
+        let tag_name = element.tag_name();
+
+        if placement.reparses_differently(&tag_name) {
+            self.force_remove_element(element);
+            return Ok(true);
+        }
+
+        let binding = self.0.borrow();
+        let Some(foreign_sanitizer) = binding.foreign_sanitizer(placement.namespace) else {
+            return Ok(false);
+        };
+
+        if !foreign_sanitizer.allows_element(&tag_name) {
+            // unwrapping these would move their contents into another namespace
+            if tag_name == placement.namespace.root() || placement.is_integration_point(&tag_name) {
+                self.remove_element(element, false, Self::SELMA_SANITIZER_REMOVE_CONTENTS);
+            } else {
+                self.remove_element(element, false, 0);
+            }
+            Self::check_if_end_tag_needs_removal(element);
+            return Ok(true);
+        }
+
+        let attributes: Vec<(String, String)> = element
+            .attributes()
+            .iter()
+            .map(|a| (a.name(), a.value()))
+            .collect();
+
+        for (attr_name, attr_val) in attributes {
+            if attr_name.starts_with("<!--") {
+                self.force_remove_element(element);
+                return Ok(true);
+            }
+
+            let x = escapist::unescape_html(attr_val.trim_start().as_bytes());
+            let mut unescaped_attr_val = String::from_utf8_lossy(&x).to_string();
+
+            let mut keep = foreign_sanitizer.allows_attribute(&tag_name, &attr_name)
+                && !foreign::references_external_resource(&attr_name, &unescaped_attr_val);
+            if keep && attr_name == "style" {
+                if let Some(css) = &binding.css {
+                    unescaped_attr_val = css.sanitize_declarations(&unescaped_attr_val);
+                    keep = !unescaped_attr_val.is_empty();
+                }
+            }
+
+            if keep {
+                Self::write_attribute(&binding, element, &attr_name, unescaped_attr_val)?;
+            } else {
+                element.remove_attribute(&attr_name);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Whether `attr_name` is allowed, where entries like `data-*` match any safe suffix.
+    fn is_attribute_allowed(allowed_attrs: &[String], attr_name: &str) -> bool {
+        allowed_attrs.iter().any(|allowed_attr| {
+            let Some(prefix) = allowed_attr.strip_suffix('*') else {
+                return allowed_attr == attr_name;
+            };
+
+            match attr_name.strip_prefix(prefix) {
+                Some(rest) if !prefix.is_empty() && !rest.is_empty() => {
+                    rest.chars().all(|c| {
+                        c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.')
+                    }) && !Self::is_dangerous_attribute(attr_name)
+                }
+                _ => false,
+            }
+        })
+    }
+
+    fn is_dangerous_attribute(attr_name: &str) -> bool {
+        const DANGEROUS_ATTRIBUTES: [&str; 4] = ["is", "srcdoc", "style", "xmlns"];
+
+        attr_name.starts_with("on")
+            || attr_name.starts_with("xmlns")
+            || DANGEROUS_ATTRIBUTES.contains(&attr_name)
+            || url::URL_ATTRIBUTES.contains(&attr_name)
+            || url::SRCSET_ATTRIBUTES
+                .iter()
+                .any(|(srcset_attr, _)| *srcset_attr == attr_name)
+    }
+
+    /// Decides whether an attribute survives. Class and style values may be
+    /// rewritten in place, in which case `attr_val` holds what to write back.
+    fn should_keep_attribute(
+        binding: &Sanitizer,
         element_sanitizer: &ElementSanitizer,
         attr_name: &String,
-        attr_val: &String,
+        attr_val: &mut String,
     ) -> Result<bool, AttributeNameError> {
         let mut allowed: bool = false;
-        let element_allowed_attrs = element_sanitizer.allowed_attrs.contains(attr_name);
-        let sanitizer_allowed_attrs = binding.allowed_attrs.contains(attr_name);
+        let element_allowed_attrs =
+            Self::is_attribute_allowed(&element_sanitizer.allowed_attrs, attr_name);
+        let sanitizer_allowed_attrs = Self::is_attribute_allowed(&binding.allowed_attrs, attr_name);
 
         if element_allowed_attrs {
             allowed = true;
@@ -360,77 +1454,141 @@ impl SelmaSanitizer {
             return Ok(false);
         }
 
+        if let Some(constraint) = element_sanitizer.value_constraints.get(attr_name) {
+            if !constraint.allows(attr_val) {
+                return Ok(false);
+            }
+        }
+
+        // every image candidate is checked on its own, so that one allowed
+        // URL can't carry along others
+        if let Some((_, fallback_attr)) = url::SRCSET_ATTRIBUTES
+            .iter()
+            .find(|(srcset_attr, _)| srcset_attr == attr_name)
+        {
+            let protocol_sanitizers = &element_sanitizer.protocol_sanitizers;
+            let protocols = protocol_sanitizers
+                .get(attr_name)
+                .or_else(|| protocol_sanitizers.get(*fallback_attr));
+            let data_uri_policies = &element_sanitizer.data_uri_policies;
+            let data_uri_policy = data_uri_policies
+                .get(attr_name)
+                .or_else(|| data_uri_policies.get(*fallback_attr));
+            let sanitized_srcset = url::sanitize_srcset(attr_val, |candidate| {
+                let resolved = binding.resolve_url(candidate);
+                let is_allowed = match (protocols, data_uri_policy) {
+                    (_, Some(policy)) if data_uri::is_data_uri(candidate) => {
+                        policy.allows(candidate)
+                    }
+                    (Some(protocols), _) => url::has_allowed_protocol(protocols, &resolved),
+                    (None, _) => url::is_relative(candidate),
+                };
+                is_allowed.then_some(resolved)
+            });
+            if sanitized_srcset.is_empty() {
+                return Ok(false);
+            }
+            *attr_val = sanitized_srcset;
+            return Ok(true);
+        }
+
+        // URLs in styles are checked against the CSS protocols instead
+        if attr_name == "style" {
+            if let Some(css) = &binding.css {
+                let sanitized_style = css.sanitize_declarations(attr_val);
+                if sanitized_style.is_empty() {
+                    return Ok(false);
+                }
+                *attr_val = sanitized_style;
+                return Ok(true);
+            }
+        }
+
+        // resolved URLs are checked against protocol lists, but without one,
+        // only URLs that were relative to begin with are kept
+        let resolved = binding
+            .resolves_urls_in(attr_name)
+            .then(|| binding.resolve_url(attr_val));
         let protocol_sanitizer_values = element_sanitizer.protocol_sanitizers.get(attr_name);
-        match protocol_sanitizer_values {
-            None => {
-                // has a protocol, but no sanitization list
-                if !attr_val.is_empty() && Self::has_protocol(attr_val) {
+        let data_uri_policy = element_sanitizer.data_uri_policies.get(attr_name);
+        match (protocol_sanitizer_values, data_uri_policy) {
+            // `data:` URLs are up to the attribute's policy for them, if it has one
+            (_, Some(policy)) if data_uri::is_data_uri(attr_val) => {
+                if !policy.allows(attr_val) {
+                    return Ok(false);
+                }
+            }
+            (None, _) => {
+                // without a protocol list, URL attributes only allow relative
+                // URLs, and so does any other attribute that holds a URL
+                let holds_url =
+                    url::URL_ATTRIBUTES.contains(&attr_name.as_str()) || attr_val.contains("://");
+                if holds_url && !url::is_relative(attr_val) {
                     return Ok(false);
                 }
             }
-            Some(protocol_sanitizer_values) => {
-                if !attr_val.is_empty()
-                    && !Self::has_allowed_protocol(protocol_sanitizer_values, attr_val)
-                {
+            (Some(protocol_sanitizer_values), _) => {
+                let url = resolved.as_deref().unwrap_or(attr_val);
+                if !url.is_empty() && !url::has_allowed_protocol(protocol_sanitizer_values, url) {
                     return Ok(false);
                 }
             }
         }
+        if let Some(resolved) = resolved {
+            *attr_val = resolved;
+        }
 
         if attr_name == "class" {
-            return Self::sanitize_class_attribute(
+            return Ok(Self::sanitize_class_attribute(
                 binding,
-                element,
                 element_sanitizer,
-                attr_name,
                 attr_val,
-            );
+            ));
         }
 
         Ok(true)
     }
 
-    fn has_protocol(attr_val: &str) -> bool {
-        attr_val.contains("://")
-    }
-
-    fn has_allowed_protocol(protocols_allowed: &[String], attr_val: &String) -> bool {
-        // FIXME: is there a more idiomatic way to do this?
-        let mut pos: usize = 0;
-        let mut chars = attr_val.chars();
-        let len = attr_val.len();
-
-        for (i, c) in attr_val.chars().enumerate() {
-            if c != ':' && c != '/' && c != '#' && pos + 1 < len {
-                pos = i + 1;
+    /// Prefixes `id` and `name` values, and everything that points at them,
+    /// like same-document links, `label[for]`, and ARIA relationships, so
+    /// that user content can't clobber globals like `window.forms` or
+    /// `document.__proto__`.
+    fn prefix_id_reference(id_prefix: &str, attr_name: &str, attr_val: &mut String) {
+        let prefix = |id: &str| {
+            if id.starts_with(id_prefix) {
+                id.to_string()
             } else {
-                break;
+                format!("{id_prefix}{id}")
             }
-        }
-
-        let char = chars.nth(pos).unwrap();
-
-        if char == '/' {
-            return protocols_allowed.contains(&"/".to_string());
-        }
+        };
 
-        if char == '#' {
-            return protocols_allowed.contains(&"#".to_string());
+        match attr_name {
+            "id" | "name" | "aria-activedescendant" | "aria-details" | "aria-errormessage" => {
+                *attr_val = prefix(attr_val);
+            }
+            // these hold space-separated lists of IDs
+            "for" | "headers" | "aria-controls" | "aria-describedby" | "aria-flowto"
+            | "aria-labelledby" | "aria-owns" => {
+                *attr_val = attr_val
+                    .split_ascii_whitespace()
+                    .map(prefix)
+                    .collect::<Vec<String>>()
+                    .join(" ");
+            }
+            "href" | "xlink:href" | "usemap" => {
+                if let Some(fragment) = attr_val.strip_prefix('#').filter(|f| !f.is_empty()) {
+                    *attr_val = format!("#{}", prefix(fragment));
+                }
+            }
+            _ => {}
         }
-
-        // Allow protocol name to be case-insensitive
-        let protocol = attr_val[0..pos].to_lowercase();
-
-        protocols_allowed.contains(&protocol.to_lowercase())
     }
 
     fn sanitize_class_attribute(
         binding: &Sanitizer,
-        element: &mut Element,
         element_sanitizer: &ElementSanitizer,
-        attr_name: &str,
-        attr_val: &str,
-    ) -> Result<bool, lol_html::errors::AttributeNameError> {
+        attr_val: &mut String,
+    ) -> bool {
         let allowed_global = &binding.allowed_classes;
 
         let mut valid_classes: Vec<String> = vec![];
@@ -439,7 +1597,7 @@ impl SelmaSanitizer {
 
         // No class filters, so everything goes through
         if allowed_global.is_empty() && allowed_local.is_empty() {
-            return Ok(true);
+            return true;
         }
 
         let attr_value = attr_val.trim_start();
@@ -447,50 +1605,49 @@ impl SelmaSanitizer {
             .split_whitespace()
             .map(|s| s.to_string())
             .for_each(|class| {
-                if allowed_global.contains(&class) || allowed_local.contains(&class) {
+                if allowed_global.allows(&class) || allowed_local.allows(&class) {
                     valid_classes.push(class);
                 }
             });
 
         if valid_classes.is_empty() {
-            return Ok(false);
+            return false;
         }
 
-        match element.set_attribute(attr_name, valid_classes.join(" ").as_str()) {
-            Ok(_) => Ok(true),
-            Err(err) => Err(err),
-        }
+        *attr_val = valid_classes.join(" ");
+        true
     }
 
     pub fn allow_element(&self, element: &mut Element) -> bool {
-        let tag = crate::tags::Tag::tag_from_element(element);
-        let flags: u8 = self.0.borrow().flags[tag.index];
+        let flags: u8 = self.0.borrow().flags_for(&element.tag_name());
 
         (flags & Self::SELMA_SANITIZER_ALLOW) == 0
     }
 
     pub fn try_remove_element(&self, element: &mut Element) -> bool {
         let tag = crate::tags::Tag::tag_from_element(element);
-        let flags: u8 = self.0.borrow().flags[tag.index];
+        let flags: u8 = self.0.borrow().flags_for(&element.tag_name());
 
         let should_remove = !element.removed() && self.allow_element(element);
 
         if should_remove {
-            if crate::tags::Tag::has_text_content(tag) {
-                Self::remove_element(
+            if (flags & Self::SELMA_SANITIZER_ESCAPE) != 0 {
+                Self::escape_element(element, tag.self_closing);
+            } else if crate::tags::Tag::has_text_content(tag) {
+                self.remove_element(
                     element,
                     tag.self_closing,
                     Self::SELMA_SANITIZER_REMOVE_CONTENTS,
                 );
             } else {
-                Self::remove_element(element, tag.self_closing, flags);
+                self.remove_element(element, tag.self_closing, flags);
             }
 
             Self::check_if_end_tag_needs_removal(element);
         } else {
             // anything in <iframe> must be removed, if it's kept
             if crate::tags::Tag::is_iframe(tag) {
-                if self.0.borrow().flags[tag.index] != 0 {
+                if flags != 0 {
                     element.set_inner_content(" ", ContentType::Text);
                 } else {
                     element.set_inner_content("", ContentType::Text);
@@ -501,12 +1658,61 @@ impl SelmaSanitizer {
         should_remove
     }
 
-    fn remove_element(element: &mut Element, self_closing: bool, flags: u8) {
+    /// In a fragment, `<html>` and `<body>` are unwrapped and `<head>` is
+    /// removed with its contents, whatever the allowlist says. Returns
+    /// whether `element` was one of them.
+    pub fn strip_document_element(&self, element: &mut Element) -> bool {
+        match element.tag_name().as_str() {
+            "html" | "body" => element.remove_and_keep_content(),
+            "head" => {
+                element.remove();
+                self.0.borrow().removed_contents.set(true);
+            }
+            _ => return false,
+        }
+
+        Self::check_if_end_tag_needs_removal(element);
+        true
+    }
+
+    /// Writes a disallowed element's tags out as text, like `&lt;b&gt;`, and
+    /// keeps its contents.
+    fn escape_element(element: &mut Element, self_closing: bool) {
+        let tag_name = element.tag_name();
+
+        let mut start_tag = format!("<{tag_name}");
+        for attr in element.attributes() {
+            start_tag.push(' ');
+            start_tag.push_str(&attr.name());
+            // quoted the way it would be in markup, since the text itself is
+            // escaped again when it's written out
+            let value = escapist::unescape_html(attr.value().as_bytes());
+            let value = String::from_utf8_lossy(&value)
+                .replace('&', "&amp;")
+                .replace('"', "&quot;");
+            if !value.is_empty() {
+                start_tag.push_str(&format!("=\"{value}\""));
+            }
+        }
+        if element.is_self_closing() {
+            start_tag.push_str(" /");
+        }
+        start_tag.push('>');
+
+        element.before(&start_tag, ContentType::Text);
+        if !self_closing && element.can_have_content() {
+            element.after(&format!("</{tag_name}>"), ContentType::Text);
+        }
+        element.remove_and_keep_content();
+    }
+
+    fn remove_element(&self, element: &mut Element, self_closing: bool, flags: u8) {
         let wrap_whitespace = (flags & Self::SELMA_SANITIZER_WRAP_WHITESPACE) != 0;
         let remove_contents = (flags & Self::SELMA_SANITIZER_REMOVE_CONTENTS) != 0;
 
         if remove_contents {
             element.remove();
+            self.0.borrow().removed_contents.set(true);
         } else {
             if wrap_whitespace {
                 if self_closing {
@@ -520,22 +1726,28 @@ impl SelmaSanitizer {
         }
     }
 
+    /// Whether the last element removed along with its contents hasn't been
+    /// asked about yet.
+    pub fn take_removed_contents(&self) -> bool {
+        self.0.borrow().removed_contents.replace(false)
+    }
+
     pub fn force_remove_element(&self, element: &mut Element) {
         let tag = crate::tags::Tag::tag_from_element(element);
         let self_closing = tag.self_closing;
-        Self::remove_element(element, self_closing, Self::SELMA_SANITIZER_REMOVE_CONTENTS);
+        self.remove_element(element, self_closing, Self::SELMA_SANITIZER_REMOVE_CONTENTS);
         Self::check_if_end_tag_needs_removal(element);
     }
 
     fn check_if_end_tag_needs_removal(element: &mut Element) {
         if element.removed() && !crate::tags::Tag::tag_from_element(element).self_closing {
-            element
-                .end_tag_handlers()
-                .unwrap()
-                .push(Box::new(move |end| {
+            // foreign elements like `<path />` can close themselves
+            if let Some(end_tag_handlers) = element.end_tag_handlers() {
+                end_tag_handlers.push(Box::new(move |end| {
                     Self::remove_end_tag(end);
                     Ok(())
                 }));
+            }
         }
     }
 
@@ -556,8 +1768,29 @@ impl SelmaSanitizer {
 pub fn init(m_selma: RModule) -> Result<(), magnus::Error> {
     let c_sanitizer = m_selma.define_class("Sanitizer", Default::default())?;
 
-    c_sanitizer.define_singleton_method("new", function!(SelmaSanitizer::new, -1))?;
+    c_sanitizer.define_singleton_method("new", function!(SelmaSanitizer::rb_new, -1))?;
+    c_sanitizer.define_singleton_method("from_json", function!(SelmaSanitizer::from_json, 1))?;
     c_sanitizer.define_method("config", method!(SelmaSanitizer::get_config, 0))?;
+    c_sanitizer.define_method("to_policy_json", method!(SelmaSanitizer::to_policy_json, 0))?;
+
+    c_sanitizer.define_method("sanitize_html", method!(SelmaSanitizer::sanitize_html, 1))?;
+    c_sanitizer.define_method(
+        "sanitize_document_html",
+        method!(SelmaSanitizer::sanitize_document_html, 1),
+    )?;
+
+    c_sanitizer.define_method("set_configured", method!(SelmaSanitizer::set_configured, 1))?;
+    c_sanitizer.define_method("configured", method!(SelmaSanitizer::get_configured, 0))?;
+    c_sanitizer.define_singleton_method(
+        "known_element?",
+        function!(SelmaSanitizer::is_known_element, 1),
+    )?;
+    c_sanitizer.define_singleton_method(
+        "valid_element_name?",
+        function!(SelmaSanitizer::is_valid_element_name, 1),
+    )?;
+    c_sanitizer
+        .define_singleton_method("pattern_error", function!(SelmaSanitizer::pattern_error, 1))?;
 
     c_sanitizer.define_method("set_flag", method!(SelmaSanitizer::set_flag, 3))?;
     c_sanitizer.define_method("set_all_flags", method!(SelmaSanitizer::set_all_flags, 2))?;
@@ -570,20 +1803,45 @@ pub fn init(m_selma: RModule) -> Result<(), magnus::Error> {
         "escape_tagfilter",
         method!(SelmaSanitizer::get_escape_tagfilter, 0),
     )?;
+    c_sanitizer.define_method(
+        "set_gfm_tagfilter",
+        method!(SelmaSanitizer::set_gfm_tagfilter, 1),
+    )?;
+    c_sanitizer.define_method(
+        "gfm_tagfilter",
+        method!(SelmaSanitizer::get_gfm_tagfilter, 0),
+    )?;
 
     c_sanitizer.define_method(
         "set_allow_comments",
         method!(SelmaSanitizer::set_allow_comments, 1),
     )?;
+    c_sanitizer.define_method(
+        "set_comment_pattern",
+        method!(SelmaSanitizer::set_comment_pattern, 2),
+    )?;
     c_sanitizer.define_method(
         "allow_comments",
         method!(SelmaSanitizer::get_allow_comments, 0),
     )?;
 
+    c_sanitizer.define_method(
+        "set_remove_whitespace_only_elements",
+        method!(SelmaSanitizer::set_remove_whitespace_only_elements, 1),
+    )?;
+    c_sanitizer.define_method(
+        "remove_whitespace_only_elements",
+        method!(SelmaSanitizer::get_remove_whitespace_only_elements, 0),
+    )?;
+
     c_sanitizer.define_method(
         "set_allow_doctype",
         method!(SelmaSanitizer::set_allow_doctype, 1),
     )?;
+    c_sanitizer.define_method(
+        "set_doctype_policy",
+        method!(SelmaSanitizer::set_doctype_policy, 1),
+    )?;
     c_sanitizer.define_method(
         "allow_doctype",
         method!(SelmaSanitizer::get_allow_doctype, 0),
@@ -594,15 +1852,127 @@ pub fn init(m_selma: RModule) -> Result<(), magnus::Error> {
         method!(SelmaSanitizer::set_allowed_attribute, 3),
     )?;
 
+    c_sanitizer.define_method(
+        "set_required_attribute",
+        method!(SelmaSanitizer::set_required_attribute, 3),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_required_attribute_action",
+        method!(SelmaSanitizer::set_required_attribute_action, 2),
+    )?;
+
     c_sanitizer.define_method(
         "set_allowed_class",
         method!(SelmaSanitizer::set_allowed_class, 3),
     )?;
 
+    c_sanitizer.define_method(
+        "set_allowed_class_pattern",
+        method!(SelmaSanitizer::set_allowed_class_pattern, 4),
+    )?;
+
     c_sanitizer.define_method(
         "set_allowed_protocols",
-        method!(SelmaSanitizer::set_allowed_protocols, 3),
+        method!(SelmaSanitizer::set_allowed_protocols, 4),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_allowed_foreign_element",
+        method!(SelmaSanitizer::set_allowed_foreign_element, 3),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_allowed_foreign_attribute",
+        method!(SelmaSanitizer::set_allowed_foreign_attribute, 4),
+    )?;
+
+    c_sanitizer.define_method(
+        "allowed_element_names",
+        method!(SelmaSanitizer::get_allowed_elements, 0),
+    )?;
+
+    c_sanitizer.define_method(
+        "allowed_attribute_names",
+        method!(SelmaSanitizer::get_allowed_attributes, 1),
+    )?;
+
+    c_sanitizer.define_method(
+        "allowed_protocol_names",
+        method!(SelmaSanitizer::get_allowed_protocols, 2),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_allowed_attribute_values",
+        method!(SelmaSanitizer::set_allowed_attribute_values, 3),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_attribute_value_pattern",
+        method!(SelmaSanitizer::set_attribute_value_pattern, 4),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_attribute_value_max_length",
+        method!(SelmaSanitizer::set_attribute_value_max_length, 3),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_data_uri_policy",
+        method!(SelmaSanitizer::set_data_uri_policy, 4),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_allowed_css_property",
+        method!(SelmaSanitizer::set_allowed_css_property, 2),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_allowed_css_protocols",
+        method!(SelmaSanitizer::set_allowed_css_protocols, 1),
+    )?;
+
+    c_sanitizer.define_method("set_css_scope", method!(SelmaSanitizer::set_css_scope, 1))?;
+
+    c_sanitizer.define_method("set_id_prefix", method!(SelmaSanitizer::set_id_prefix, 1))?;
+
+    c_sanitizer.define_method("set_base_url", method!(SelmaSanitizer::set_base_url, 2))?;
+
+    c_sanitizer.define_method("set_link_rel", method!(SelmaSanitizer::set_link_rel, 2))?;
+
+    c_sanitizer.define_method(
+        "set_allowed_link_targets",
+        method!(SelmaSanitizer::set_allowed_link_targets, 1),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_internal_link_hosts",
+        method!(SelmaSanitizer::set_internal_link_hosts, 1),
+    )?;
+
+    c_sanitizer.define_method("set_base_hosts", method!(SelmaSanitizer::set_base_hosts, 1))?;
+
+    c_sanitizer.define_method(
+        "set_allowed_link_rels",
+        method!(SelmaSanitizer::set_allowed_link_rels, 1),
+    )?;
+
+    c_sanitizer.define_method(
+        "set_stylesheet_hosts",
+        method!(SelmaSanitizer::set_stylesheet_hosts, 1),
     )?;
 
     Ok(())
 }
+
+pub mod classes;
+pub mod css;
+pub mod data_uri;
+pub mod document;
+pub mod empty_elements;
+pub mod foreign;
+pub mod head;
+pub mod links;
+pub mod policy;
+pub mod tagfilter;
+pub mod url;
@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+
+/// Allowed class names, along with globs (like `pl-*`) and patterns, which
+/// are compiled into a single regex so that each class is only matched once.
+#[derive(Clone, Debug, Default)]
+pub struct ClassAllowlist {
+    names: HashSet<String>,
+    patterns: Vec<(String, bool)>,
+    matcher: Option<Regex>,
+}
+
+impl ClassAllowlist {
+    /// Names containing `*` are globs, where `*` stands for any run of characters.
+    pub fn set_allowed(&mut self, class_name: &str, allow: bool) -> Result<(), regex::Error> {
+        if class_name.contains('*') {
+            let pattern = class_name
+                .split('*')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".*");
+            return self.set_allowed_pattern(&pattern, false, allow);
+        }
+
+        if allow {
+            self.names.insert(class_name.to_string());
+        } else {
+            self.names.remove(class_name);
+        }
+
+        Ok(())
+    }
+
+    /// Allows classes that match all of `pattern`.
+    pub fn set_allowed_pattern(
+        &mut self,
+        pattern: &str,
+        case_insensitive: bool,
+        allow: bool,
+    ) -> Result<(), regex::Error> {
+        // each pattern is checked on its own first, so that errors point at it
+        Regex::new(pattern)?;
+        let pattern = (pattern.to_string(), case_insensitive);
+
+        let mut patterns = self.patterns.clone();
+        if allow {
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        } else {
+            patterns.retain(|p| *p != pattern);
+        }
+
+        self.matcher = if patterns.is_empty() {
+            None
+        } else {
+            let alternatives = patterns
+                .iter()
+                .map(|(pattern, case_insensitive)| {
+                    let flags = if *case_insensitive { "(?i)" } else { "" };
+                    format!("(?:{flags}{pattern})")
+                })
+                .collect::<Vec<_>>();
+            Some(Regex::new(&format!(r"\A(?:{})\z", alternatives.join("|")))?)
+        };
+        self.patterns = patterns;
+
+        Ok(())
+    }
+
+    /// Allowed names, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.names.iter().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Allowed patterns, globs included, along with whether they're case-insensitive.
+    pub fn patterns(&self) -> &[(String, bool)] {
+        &self.patterns
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.matcher.is_none()
+    }
+
+    pub fn allows(&self, class_name: &str) -> bool {
+        self.names.contains(class_name)
+            || self
+                .matcher
+                .as_ref()
+                .is_some_and(|matcher| matcher.is_match(class_name))
+    }
+}
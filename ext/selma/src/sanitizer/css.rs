@@ -0,0 +1,628 @@
+use crate::sanitizer::url;
+
+/// Sanitizes CSS against an allowlist of properties and `url()` protocols.
+#[derive(Clone, Debug, Default)]
+pub struct CssSanitizer {
+    allowed_properties: Vec<String>,
+    allowed_protocols: Vec<String>,
+    scope: Option<String>,
+}
+
+impl CssSanitizer {
+    pub fn set_allowed_property(&mut self, property: &str, allow: bool) {
+        let property = property.to_ascii_lowercase();
+        if allow {
+            if !self.allowed_properties.contains(&property) {
+                self.allowed_properties.push(property);
+            }
+        } else {
+            self.allowed_properties.retain(|p| p != &property);
+        }
+    }
+
+    /// Protocols are stored the same way as element protocols: a scheme name,
+    /// or one of the markers in `url` for relative URLs.
+    pub fn set_allowed_protocol(&mut self, protocol: &str) {
+        let protocol = protocol.to_ascii_lowercase();
+        if !self.allowed_protocols.contains(&protocol) {
+            self.allowed_protocols.push(protocol);
+        }
+    }
+
+    /// A selector (such as `.user-theme`) that every rule in a stylesheet is nested under.
+    pub fn set_scope(&mut self, scope: Option<String>) {
+        self.scope = scope.filter(|scope| !scope.trim().is_empty());
+    }
+
+    pub fn allowed_properties(&self) -> &[String] {
+        &self.allowed_properties
+    }
+
+    pub fn allowed_protocols(&self) -> &[String] {
+        &self.allowed_protocols
+    }
+
+    pub fn scope(&self) -> Option<&str> {
+        self.scope.as_deref()
+    }
+
+    /// Filters a declaration list, such as the contents of a `style` attribute,
+    /// and returns it normalized as `property: value;` pairs. Declarations whose
+    /// property isn't allowed, or whose value looks dangerous, are dropped.
+    pub fn sanitize_declarations(&self, css: &str) -> String {
+        let css = strip_comments(css);
+
+        split_top_level(&css, ';')
+            .into_iter()
+            .filter_map(|declaration| self.sanitize_declaration(declaration))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Filters a whole stylesheet, such as the contents of a `<style>` element.
+    /// Rules keep only their allowed declarations, and are nested under the
+    /// scope, if there is one. `@media`-like blocks are filtered recursively,
+    /// `@import` and `@font-face` only survive if their URLs are allowed, and
+    /// every other at-rule is dropped.
+    pub fn sanitize_stylesheet(&self, css: &str) -> String {
+        let css = strip_comments(css);
+
+        self.sanitize_rules(&css, 0).join("\n")
+    }
+
+    fn sanitize_rules(&self, css: &str, depth: usize) -> Vec<String> {
+        let mut rules = vec![];
+        let mut rest = css;
+
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+
+            // stray separators and legacy HTML comment markers are skipped, like browsers do
+            if let Some(stripped) = [";", "<!--", "-->"]
+                .iter()
+                .find_map(|m| rest.strip_prefix(m))
+            {
+                rest = stripped;
+                continue;
+            }
+
+            let Some(end) = find_top_level(rest, &[';', '{']) else {
+                break;
+            };
+            let prelude = rest[..end].trim();
+
+            if rest[end..].starts_with(';') {
+                // a statement at-rule, such as `@import` or `@charset`
+                if let Some(rule) = self.sanitize_statement_at_rule(prelude) {
+                    rules.push(rule);
+                }
+                rest = &rest[end + 1..];
+                continue;
+            }
+
+            let block_start = end + 1;
+            let block_end = closing_brace(&rest[block_start..]).map(|i| block_start + i);
+            let block = &rest[block_start..block_end.unwrap_or(rest.len())];
+            rest = match block_end {
+                Some(block_end) => &rest[block_end + 1..],
+                None => "",
+            };
+
+            let rule = if prelude.starts_with('@') {
+                self.sanitize_block_at_rule(prelude, block, depth)
+            } else {
+                self.sanitize_style_rule(prelude, block)
+            };
+            if let Some(rule) = rule {
+                rules.push(rule);
+            }
+        }
+
+        rules
+    }
+
+    fn sanitize_statement_at_rule(&self, prelude: &str) -> Option<String> {
+        let (name, condition) = split_at_rule(prelude);
+        if name != "import" || !self.is_safe_value(condition) {
+            return None;
+        }
+
+        // `@import "foo.css"` is as much a URL as `@import url(foo.css)`
+        let decoded = decode_escapes(condition);
+        let url = match url_arguments(&decoded.to_ascii_lowercase())
+            .into_iter()
+            .next()
+        {
+            Some(url) => url,
+            None => quoted_strings(&decoded).first()?.to_string(),
+        };
+        if !self.has_allowed_protocol(&url) {
+            return None;
+        }
+
+        Some(format!("@import {};", collapse_whitespace(condition)))
+    }
+
+    fn sanitize_block_at_rule(&self, prelude: &str, block: &str, depth: usize) -> Option<String> {
+        const MAX_DEPTH: usize = 16;
+
+        let (name, condition) = split_at_rule(prelude);
+        if !condition.is_empty() && !self.is_safe_value(condition) {
+            return None;
+        }
+        let condition = collapse_whitespace(condition);
+
+        let body = match name.as_str() {
+            "media" | "supports" | "container" | "layer" if depth < MAX_DEPTH => {
+                let rules = self.sanitize_rules(block, depth + 1);
+                if rules.is_empty() {
+                    return None;
+                }
+                rules.join("\n")
+            }
+            "font-face" => {
+                // a font with an untrusted source goes away entirely
+                if !self.is_safe_value(block) {
+                    return None;
+                }
+                let declarations = self.sanitize_declarations_with(block, FONT_DESCRIPTORS);
+                if declarations.is_empty() {
+                    return None;
+                }
+                declarations
+            }
+            "keyframes" | "-webkit-keyframes" => {
+                let frames = self.sanitize_keyframes(block);
+                if frames.is_empty() {
+                    return None;
+                }
+                frames.join("\n")
+            }
+            _ => return None,
+        };
+
+        if condition.is_empty() {
+            Some(format!("@{name} {{ {body} }}"))
+        } else {
+            Some(format!("@{name} {condition} {{ {body} }}"))
+        }
+    }
+
+    fn sanitize_keyframes(&self, css: &str) -> Vec<String> {
+        let mut frames = vec![];
+        let mut rest = css;
+
+        while let Some(open) = find_top_level(rest, &['{']) {
+            let selector = collapse_whitespace(&rest[..open]);
+            let block_start = open + 1;
+            let block_end = closing_brace(&rest[block_start..]).map(|i| block_start + i);
+            let block = &rest[block_start..block_end.unwrap_or(rest.len())];
+            rest = match block_end {
+                Some(block_end) => &rest[block_end + 1..],
+                None => "",
+            };
+
+            let is_keyframe_selector = selector.split(',').all(|s| {
+                let s = s.trim().to_ascii_lowercase();
+                s == "from"
+                    || s == "to"
+                    || s.strip_suffix('%').is_some_and(|n| {
+                        !n.is_empty() && n.chars().all(|c| c.is_ascii_digit() || c == '.')
+                    })
+            });
+            let declarations = self.sanitize_declarations(block);
+            if is_keyframe_selector && !declarations.is_empty() {
+                frames.push(format!("{selector} {{ {declarations} }}"));
+            }
+        }
+
+        frames
+    }
+
+    fn sanitize_style_rule(&self, selectors: &str, block: &str) -> Option<String> {
+        if selectors.is_empty() || selectors.contains(['<', '@', '{', '}']) {
+            return None;
+        }
+
+        let declarations = self.sanitize_declarations(block);
+        if declarations.is_empty() {
+            return None;
+        }
+
+        let selectors = split_top_level(selectors, ',')
+            .into_iter()
+            .map(|selector| self.scope_selector(&collapse_whitespace(selector)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Some(format!("{selectors} {{ {declarations} }}"))
+    }
+
+    fn scope_selector(&self, selector: &str) -> String {
+        match &self.scope {
+            None => selector.to_string(),
+            Some(scope) => match selector.to_ascii_lowercase().as_str() {
+                // the document root becomes the scope itself
+                "html" | "body" | ":root" => scope.to_string(),
+                _ => format!("{scope} {selector}"),
+            },
+        }
+    }
+
+    fn sanitize_declarations_with(&self, css: &str, extra_properties: &[&str]) -> String {
+        split_top_level(css, ';')
+            .into_iter()
+            .filter_map(|declaration| {
+                let (name, _) = declaration.split_once(':')?;
+                let name = name.trim().to_ascii_lowercase();
+                if extra_properties.contains(&name.as_str()) {
+                    self.sanitize_declaration_value(&name, declaration)
+                } else {
+                    self.sanitize_declaration(declaration)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn sanitize_declaration(&self, declaration: &str) -> Option<String> {
+        let (name, _) = declaration.split_once(':')?;
+
+        let name = name.trim().to_ascii_lowercase();
+        if !is_property_name(&name) || !self.allowed_properties.contains(&name) {
+            return None;
+        }
+
+        self.sanitize_declaration_value(&name, declaration)
+    }
+
+    fn sanitize_declaration_value(&self, name: &str, declaration: &str) -> Option<String> {
+        let (_, value) = declaration.split_once(':')?;
+
+        let value = collapse_whitespace(value);
+        if value.is_empty() || !self.is_safe_value(&value) {
+            return None;
+        }
+
+        Some(format!("{name}: {value};"))
+    }
+
+    fn is_safe_value(&self, value: &str) -> bool {
+        // a declaration can't break out into a new rule
+        if value.contains(['{', '}', '<']) {
+            return false;
+        }
+
+        // check what the browser will actually see, so that escaped variants
+        // like `e\78 pression(` or `url(java\73 cript:...)` are caught, too
+        let decoded = decode_escapes(value).to_ascii_lowercase();
+        let compact: String = decoded
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .collect();
+
+        if compact.contains("expression(")
+            || compact.contains("javascript:")
+            || compact.contains("vbscript:")
+            || compact.contains("-moz-binding")
+        {
+            return false;
+        }
+
+        url_arguments(&decoded)
+            .iter()
+            .all(|url| self.has_allowed_protocol(url))
+    }
+
+    fn has_allowed_protocol(&self, url: &str) -> bool {
+        url::has_allowed_protocol(&self.allowed_protocols, url)
+    }
+}
+
+/// Descriptors that are always allowed inside `@font-face`.
+const FONT_DESCRIPTORS: &[&str] = &[
+    "font-display",
+    "font-family",
+    "font-stretch",
+    "font-style",
+    "font-weight",
+    "src",
+    "unicode-range",
+];
+
+/// Splits `@name condition` into the lowercased name and the condition.
+fn split_at_rule(prelude: &str) -> (String, &str) {
+    let prelude = prelude.trim_start_matches('@');
+    let end = prelude
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(prelude.len());
+
+    (prelude[..end].to_ascii_lowercase(), prelude[end..].trim())
+}
+
+/// Finds the first of `needles` outside of strings, parentheses, and brackets.
+fn find_top_level(css: &str, needles: &[char]) -> Option<usize> {
+    let mut depth: usize = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for (i, c) in css.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' => escaped = true,
+            '"' | '\'' => match quote {
+                Some(q) if q == c => quote = None,
+                None => quote = Some(c),
+                _ => {}
+            },
+            _ if quote.is_some() => {}
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && needles.contains(&c) => return Some(i),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Finds the `}` closing a block whose `{` has just been consumed.
+fn closing_brace(css: &str) -> Option<usize> {
+    let mut depth: usize = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for (i, c) in css.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' => escaped = true,
+            '"' | '\'' => match quote {
+                Some(q) if q == c => quote = None,
+                None => quote = Some(c),
+                _ => {}
+            },
+            _ if quote.is_some() => {}
+            '{' => depth += 1,
+            '}' => {
+                if depth == 0 {
+                    return Some(i);
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Removes `/* ... */` comments, leaving string contents alone.
+pub(crate) fn strip_comments(css: &str) -> String {
+    let mut output = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                output.push(c);
+                if c == '\\' {
+                    if let Some(escaped) = chars.next() {
+                        output.push(escaped);
+                    }
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None => {
+                if c == '/' && chars.peek() == Some(&'*') {
+                    chars.next();
+                    let mut previous = '\0';
+                    for c in chars.by_ref() {
+                        if previous == '*' && c == '/' {
+                            break;
+                        }
+                        previous = c;
+                    }
+                    // a comment separates tokens, just like whitespace
+                    output.push(' ');
+                } else {
+                    if c == '"' || c == '\'' {
+                        quote = Some(c);
+                    } else if c == '\\' {
+                        output.push(c);
+                        if let Some(escaped) = chars.next() {
+                            output.push(escaped);
+                        }
+                        continue;
+                    }
+                    output.push(c);
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// Splits `css` on `separator`, ignoring separators inside strings,
+/// parentheses, and brackets.
+pub(crate) fn split_top_level(css: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth: usize = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in css.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' => escaped = true,
+            '"' | '\'' => match quote {
+                Some(q) if q == c => quote = None,
+                None => quote = Some(c),
+                _ => {}
+            },
+            '(' | '[' if quote.is_none() => depth += 1,
+            ')' | ']' if quote.is_none() => depth = depth.saturating_sub(1),
+            _ if c == separator && quote.is_none() && depth == 0 => {
+                parts.push(&css[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&css[start..]);
+
+    parts
+}
+
+/// Resolves CSS escape sequences (`\6a`, `\j`) into the characters they stand for.
+pub(crate) fn decode_escapes(css: &str) -> String {
+    let mut output = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        let mut hex = String::new();
+        while hex.len() < 6 {
+            match chars.peek() {
+                Some(h) if h.is_ascii_hexdigit() => {
+                    hex.push(*h);
+                    chars.next();
+                }
+                _ => break,
+            }
+        }
+
+        if hex.is_empty() {
+            match chars.next() {
+                // an escaped newline is a line continuation
+                Some('\n') | Some('\r') | Some('\x0C') | None => {}
+                Some(escaped) => output.push(escaped),
+            }
+        } else {
+            // a single whitespace character after a hex escape is part of it
+            if matches!(chars.peek(), Some(' ' | '\t' | '\n' | '\r' | '\x0C')) {
+                chars.next();
+            }
+            let code_point = u32::from_str_radix(&hex, 16).unwrap_or(0xFFFD);
+            output.push(match char::from_u32(code_point) {
+                Some(c) if code_point != 0 => c,
+                _ => '\u{FFFD}',
+            });
+        }
+    }
+
+    output
+}
+
+/// Collects the URLs referenced by `url()` and `image-set()` style functions.
+/// `css` is expected to be decoded and lowercased.
+pub(crate) fn url_arguments(css: &str) -> Vec<String> {
+    let mut urls = vec![];
+
+    for function in ["url(", "image(", "image-set(", "-webkit-image-set(", "src("] {
+        let mut rest = css;
+        while let Some(pos) = rest.find(function) {
+            // `image(` also matches the tail of `-webkit-image(`, which is fine
+            let arguments = &rest[pos + function.len()..];
+            let end = closing_paren(arguments).unwrap_or(arguments.len());
+            let arguments = &arguments[..end];
+
+            if function == "url(" || function == "src(" {
+                urls.push(unquote(arguments.trim()).to_string());
+            } else {
+                // bare strings inside image functions are URLs, too
+                urls.extend(quoted_strings(arguments).into_iter().map(String::from));
+            }
+
+            rest = &rest[pos + function.len()..];
+        }
+    }
+
+    urls
+}
+
+fn closing_paren(css: &str) -> Option<usize> {
+    let mut depth: usize = 0;
+    let mut quote: Option<char> = None;
+
+    for (i, c) in css.char_indices() {
+        match c {
+            '"' | '\'' => match quote {
+                Some(q) if q == c => quote = None,
+                None => quote = Some(c),
+                _ => {}
+            },
+            '(' if quote.is_none() => depth += 1,
+            ')' if quote.is_none() => {
+                if depth == 0 {
+                    return Some(i);
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn quoted_strings(css: &str) -> Vec<&str> {
+    let mut strings = vec![];
+    let mut quote: Option<(char, usize)> = None;
+
+    for (i, c) in css.char_indices() {
+        match quote {
+            Some((q, start)) if q == c => {
+                strings.push(&css[start..i]);
+                quote = None;
+            }
+            None if c == '"' || c == '\'' => quote = Some((c, i + 1)),
+            _ => {}
+        }
+    }
+
+    strings
+}
+
+fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    for q in ['"', '\''] {
+        if let Some(inner) = s.strip_prefix(q) {
+            return inner.strip_suffix(q).unwrap_or(inner);
+        }
+    }
+    s
+}
+
+fn is_property_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,99 @@
+use crate::sanitizer::url::UrlScheme;
+
+/// Which `data:` URLs an attribute keeps: base64-encoded ones with one of
+/// `mime_types`, no bigger than `max_size` bytes once decoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DataUriPolicy {
+    /// Lowercased MIME types, like `image/png`.
+    mime_types: Vec<String>,
+    max_size: Option<usize>,
+}
+
+impl DataUriPolicy {
+    pub fn new(mime_types: &[String], max_size: Option<usize>) -> Self {
+        DataUriPolicy {
+            mime_types: mime_types.iter().map(|m| m.to_ascii_lowercase()).collect(),
+            max_size,
+        }
+    }
+
+    pub fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+
+    pub fn max_size(&self) -> Option<usize> {
+        self.max_size
+    }
+
+    /// Whether `url` is a `data:` URL this policy keeps. Anything ambiguous,
+    /// like whitespace in the MIME type or percent-encoded base64, is dropped
+    /// rather than guessed at.
+    pub fn allows(&self, url: &str) -> bool {
+        let Some((mime_type, payload)) = parse(url) else {
+            return false;
+        };
+
+        if !self.mime_types.contains(&mime_type) {
+            return false;
+        }
+
+        match decoded_size(payload) {
+            Some(size) => !matches!(self.max_size, Some(max_size) if size > max_size),
+            None => false,
+        }
+    }
+}
+
+/// Whether `url` uses the `data` scheme, however it's written.
+pub fn is_data_uri(url: &str) -> bool {
+    UrlScheme::parse(url) == UrlScheme::Named("data".to_string())
+}
+
+/// Splits a base64 `data:` URL into its lowercased MIME type and its payload.
+fn parse(url: &str) -> Option<(String, &str)> {
+    let url = url.trim_matches(|c: char| c == ' ' || c.is_ascii_control());
+    let scheme = url.get(..5)?;
+    if !scheme.eq_ignore_ascii_case("data:") {
+        return None;
+    }
+
+    let (metadata, payload) = url[5..].split_once(',')?;
+    if metadata
+        .chars()
+        .any(|c| c.is_whitespace() || c.is_control())
+    {
+        return None;
+    }
+
+    let (metadata, encoding) = metadata.rsplit_once(';')?;
+    if !encoding.eq_ignore_ascii_case("base64") {
+        return None;
+    }
+
+    // parameters, like `charset`, don't change what the payload is
+    let mime_type = metadata.split(';').next().unwrap_or_default();
+    Some((mime_type.to_ascii_lowercase(), payload))
+}
+
+/// The decoded size of well-formed base64, which may be broken up by ASCII
+/// whitespace but must otherwise be padded and use the standard alphabet.
+fn decoded_size(payload: &str) -> Option<usize> {
+    let mut length = 0;
+    let mut padding = 0;
+
+    for c in payload.chars() {
+        match c {
+            ' ' | '\t' | '\n' | '\x0c' | '\r' => continue,
+            '=' if padding < 2 => padding += 1,
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '+' | '/' if padding == 0 => {}
+            _ => return None,
+        }
+        length += 1;
+    }
+
+    if length % 4 != 0 {
+        return None;
+    }
+
+    Some(length / 4 * 3 - padding)
+}
@@ -0,0 +1,164 @@
+use std::{cell::RefCell, rc::Rc};
+
+use lol_html::html_content::{ContentType, Element, TextChunk};
+
+/// Elements that stay in `<head>` if they come before anything else.
+const HEAD_ELEMENTS: [&str; 8] = [
+    "base", "link", "meta", "noscript", "script", "style", "template", "title",
+];
+
+/// Whether the input is a fragment, like a comment body, or a whole document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SanitizeMode {
+    /// `<html>` and `<body>` are unwrapped, `<head>` is removed along with
+    /// its contents, and doctypes are never kept.
+    Fragment,
+    /// The output always has an `<html>`, `<head>`, and `<body>`.
+    Document,
+}
+
+/// How far into the skeleton the output is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    #[default]
+    Before,
+    Html,
+    Head,
+    Body,
+}
+
+/// Fills in whichever of `<html>`, `<head>`, and `<body>` a document is
+/// missing. Those it has are kept where they are, and their end tags are
+/// written once the document ends.
+#[derive(Debug, Default)]
+pub struct DocumentSkeleton {
+    section: Section,
+    /// Open elements in `<head>` whose contents, like a `<title>`'s text,
+    /// don't start the body.
+    open_head_elements: usize,
+}
+
+impl DocumentSkeleton {
+    pub fn enter(skeleton: &Rc<RefCell<Self>>, element: &mut Element) {
+        let tag_name = element.tag_name();
+        let mut binding = skeleton.borrow_mut();
+
+        let (markup, keep_tag) = match tag_name.as_str() {
+            "html" => (String::new(), binding.section == Section::Before),
+            "head" => (
+                binding.advance(Section::Html),
+                binding.section <= Section::Html,
+            ),
+            "body" if binding.section < Section::Body => {
+                let mut markup = binding.advance(Section::Head);
+                markup.push_str("</head>");
+                (markup, true)
+            }
+            "body" => (String::new(), false),
+            _ if binding.open_head_elements > 0 => (String::new(), true),
+            _ if HEAD_ELEMENTS.contains(&tag_name.as_str()) && binding.section <= Section::Head => {
+                (binding.advance(Section::Head), true)
+            }
+            _ => (binding.advance(Section::Body), true),
+        };
+
+        if !markup.is_empty() {
+            element.before(&markup, ContentType::Html);
+        }
+
+        if !keep_tag {
+            element.remove_and_keep_content();
+            return;
+        }
+
+        match tag_name.as_str() {
+            "html" => binding.section = Section::Html,
+            "head" => binding.section = Section::Head,
+            "body" => binding.section = Section::Body,
+            _ => {}
+        }
+
+        let Some(end_tag_handlers) = element.end_tag_handlers() else {
+            return;
+        };
+
+        if matches!(tag_name.as_str(), "html" | "head" | "body") {
+            // closed once the document ends, so that nothing ends up outside them
+            end_tag_handlers.push(Box::new(|end| {
+                end.remove();
+                Ok(())
+            }));
+        } else if binding.section == Section::Head {
+            binding.open_head_elements += 1;
+
+            let end_tag_skeleton = Rc::clone(skeleton);
+            end_tag_handlers.push(Box::new(move |_end| {
+                end_tag_skeleton.borrow_mut().open_head_elements -= 1;
+                Ok(())
+            }));
+        }
+    }
+
+    /// Text other than whitespace starts the body, unless it's inside an
+    /// element in `<head>`.
+    pub fn text(&mut self, text: &mut TextChunk) {
+        if text.as_str().trim().is_empty() {
+            return;
+        }
+
+        let markup = self.start_content();
+        if !markup.is_empty() {
+            text.before(&markup, ContentType::Html);
+        }
+    }
+
+    /// An escaped element's tags are written out as text, so they start the
+    /// body just like text does.
+    pub fn escape(&mut self, element: &mut Element) {
+        let markup = self.start_content();
+        if !markup.is_empty() {
+            element.before(&markup, ContentType::Html);
+        }
+    }
+
+    /// The markup that closes the document, opening whatever it never got to.
+    pub fn finish(&mut self) -> String {
+        let mut markup = self.advance(Section::Body);
+        markup.push_str("</body></html>");
+
+        markup
+    }
+
+    /// Opens the body for content, unless it's inside an element in `<head>`.
+    fn start_content(&mut self) -> String {
+        if self.open_head_elements > 0 {
+            return String::new();
+        }
+
+        self.advance(Section::Body)
+    }
+
+    /// Opens everything up to `section`, returning the markup to do so.
+    fn advance(&mut self, section: Section) -> String {
+        let mut markup = String::new();
+
+        while self.section < section {
+            self.section = match self.section {
+                Section::Before => {
+                    markup.push_str("<html>");
+                    Section::Html
+                }
+                Section::Html => {
+                    markup.push_str("<head>");
+                    Section::Head
+                }
+                Section::Head | Section::Body => {
+                    markup.push_str("</head><body>");
+                    Section::Body
+                }
+            };
+        }
+
+        markup
+    }
+}
@@ -0,0 +1,135 @@
+use lol_html::html_content::Element;
+
+/// Elements whose start tags are being held back until it's known whether
+/// they have any content.
+#[derive(Debug, Default)]
+pub struct PendingElements {
+    stack: Vec<PendingElement>,
+    next_id: usize,
+}
+
+#[derive(Debug)]
+struct PendingElement {
+    id: usize,
+    tag_name: String,
+    start_tag: String,
+    held: Vec<Held>,
+    flushed: bool,
+}
+
+/// What's held back along with a start tag, in order.
+#[derive(Debug)]
+enum Held {
+    /// Text that doesn't count as content, which stays even if the element
+    /// doesn't, so that words on either side of it aren't run together.
+    Text(String),
+    /// A comment, which goes with the element if it's empty.
+    Comment(String),
+}
+
+impl Held {
+    fn markup(&self) -> &str {
+        match self {
+            Held::Text(markup) | Held::Comment(markup) => markup,
+        }
+    }
+}
+
+impl PendingElements {
+    /// Holds back `element`'s start tag, returning an ID to close it with.
+    pub fn push(&mut self, element: &Element) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.stack.push(PendingElement {
+            id,
+            tag_name: element.tag_name(),
+            start_tag: serialize_start_tag(element),
+            held: vec![],
+            flushed: false,
+        });
+
+        id
+    }
+
+    /// Whether there's a start tag being held back, which text that doesn't
+    /// count as content, and comments, have to be held back with, to keep
+    /// them in order.
+    pub fn is_holding(&self) -> bool {
+        self.stack.last().is_some_and(|pending| !pending.flushed)
+    }
+
+    pub fn hold_text(&mut self, text: &str) {
+        if let Some(pending) = self.stack.last_mut() {
+            pending.held.push(Held::Text(text.to_string()));
+        }
+    }
+
+    pub fn hold_comment(&mut self, comment: &str) {
+        if let Some(pending) = self.stack.last_mut() {
+            pending.held.push(Held::Comment(comment.to_string()));
+        }
+    }
+
+    /// Content has shown up, so every start tag still held back is returned,
+    /// outermost first, along with anything held back after it.
+    pub fn flush(&mut self) -> String {
+        let mut start_tags = String::new();
+
+        for pending in self.stack.iter_mut().filter(|pending| !pending.flushed) {
+            start_tags.push_str(&pending.start_tag);
+            for held in std::mem::take(&mut pending.held) {
+                start_tags.push_str(held.markup());
+            }
+            pending.flushed = true;
+        }
+
+        start_tags
+    }
+
+    /// Returns what to write in place of the element's end tag: the end tag
+    /// itself, if its start tag was written, or else the text held back with
+    /// it. Elements opened inside it that were never closed are closed with it.
+    pub fn close(&mut self, id: usize) -> Option<String> {
+        let position = self.stack.iter().position(|pending| pending.id == id)?;
+        let closed: Vec<PendingElement> = self.stack.drain(position..).collect();
+        // comments go along with the empty elements they're in
+        let held: String = closed
+            .iter()
+            .flat_map(|pending| {
+                pending
+                    .held
+                    .iter()
+                    .filter(|held| pending.flushed || matches!(held, Held::Text(_)))
+            })
+            .map(Held::markup)
+            .collect();
+
+        if closed[0].flushed {
+            return Some(format!("{}</{}>", held, closed[0].tag_name));
+        }
+
+        // the element was empty, but its text stays in order with its parent's
+        if self.is_holding() {
+            self.hold_text(&held);
+            None
+        } else {
+            Some(held)
+        }
+    }
+}
+
+fn serialize_start_tag(element: &Element) -> String {
+    let mut start_tag = format!("<{}", element.tag_name());
+
+    for attr in element.attributes() {
+        start_tag.push_str(&format!(
+            " {}=\"{}\"",
+            attr.name(),
+            attr.value().replace('"', "&quot;")
+        ));
+    }
+    start_tag.push('>');
+
+    start_tag
+}
@@ -0,0 +1,298 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use lol_html::html_content::Element;
+
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+const MATHML_NAMESPACE: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// Elements in SVG whose contents are parsed as HTML.
+const SVG_HTML_INTEGRATION_POINTS: [&str; 3] = ["desc", "foreignobject", "title"];
+
+/// Elements in MathML whose contents are parsed as HTML, except for `<mglyph>`
+/// and `<malignmark>` children, which stay in MathML.
+const MATHML_TEXT_INTEGRATION_POINTS: [&str; 5] = ["mi", "mn", "mo", "ms", "mtext"];
+
+/// Elements whose contents HTML parses as text, which foreign content parses
+/// as markup. Inside `<svg>` or `<math>` they're removed, contents and all,
+/// since the two readings are the root of most namespace confusion attacks.
+const RAW_TEXT_ELEMENTS: [&str; 10] = [
+    "iframe",
+    "noembed",
+    "noframes",
+    "noscript",
+    "plaintext",
+    "script",
+    "style",
+    "textarea",
+    "title",
+    "xmp",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Namespace {
+    Html,
+    Svg,
+    MathMl,
+}
+
+impl Namespace {
+    /// The config key for each foreign namespace.
+    pub fn from_key(key: &str) -> Option<Namespace> {
+        match key {
+            "svg" => Some(Namespace::Svg),
+            "math" => Some(Namespace::MathMl),
+            _ => None,
+        }
+    }
+
+    /// The element that starts foreign content in this namespace.
+    pub fn root(&self) -> &'static str {
+        match self {
+            Namespace::Html => "html",
+            Namespace::Svg => "svg",
+            Namespace::MathMl => "math",
+        }
+    }
+
+    fn is_integration_point(&self, tag_name: &str) -> bool {
+        match self {
+            Namespace::Html => false,
+            Namespace::Svg => SVG_HTML_INTEGRATION_POINTS.contains(&tag_name),
+            Namespace::MathMl => {
+                MATHML_TEXT_INTEGRATION_POINTS.contains(&tag_name) || tag_name == "annotation-xml"
+            }
+        }
+    }
+}
+
+/// Where an element sits with respect to `<svg>` and `<math>`.
+#[derive(Clone, Debug)]
+pub struct Placement {
+    pub namespace: Namespace,
+    /// The parent's namespace and tag name, if the element is inside `<svg>`
+    /// or `<math>`.
+    pub parent: Option<(Namespace, String)>,
+}
+
+impl Placement {
+    /// Whether this is a foreign element, or an HTML one inside foreign content.
+    pub fn is_foreign(&self) -> bool {
+        self.namespace != Namespace::Html || self.parent.is_some()
+    }
+
+    /// Whether the element's contents could be read another way, or the
+    /// element moved into another namespace, once the output is parsed again.
+    pub fn reparses_differently(&self, tag_name: &str) -> bool {
+        let is_raw_text = self.parent.is_some()
+            && RAW_TEXT_ELEMENTS.contains(&tag_name)
+            && !self.is_integration_point(tag_name);
+
+        is_raw_text || self.is_misplaced_mathml(tag_name)
+    }
+
+    /// `<mglyph>` and `<malignmark>` only belong directly inside a MathML
+    /// text integration point. Anywhere else, reparsing can move them into
+    /// another namespace, taking their contents with them.
+    fn is_misplaced_mathml(&self, tag_name: &str) -> bool {
+        if !matches!(tag_name, "mglyph" | "malignmark") {
+            return false;
+        }
+
+        !matches!(
+            &self.parent,
+            Some((Namespace::MathMl, parent)) if MATHML_TEXT_INTEGRATION_POINTS.contains(&parent.as_str())
+        ) || self.namespace != Namespace::MathMl
+    }
+
+    /// Integration points switch their contents into HTML, so unwrapping one
+    /// would move its contents into another namespace.
+    pub fn is_integration_point(&self, tag_name: &str) -> bool {
+        self.namespace.is_integration_point(tag_name)
+    }
+}
+
+/// The open elements inside `<svg>` and `<math>`. lol_html reports the
+/// namespace of most elements, but integration points like `<foreignObject>`
+/// come out as HTML, since their contents are, so their parents are tracked
+/// to tell them apart.
+#[derive(Debug, Default)]
+pub struct ForeignContent {
+    stack: Vec<OpenElement>,
+    next_id: usize,
+}
+
+#[derive(Debug)]
+struct OpenElement {
+    id: usize,
+    namespace: Namespace,
+    tag_name: String,
+}
+
+impl ForeignContent {
+    /// Works out where `element` sits, and tracks it until its end tag if it
+    /// could have children in foreign content.
+    pub fn enter(foreign_content: &Rc<RefCell<Self>>, element: &mut Element) -> Placement {
+        let tag_name = element.tag_name();
+        let placement = foreign_content.borrow().placement(element, &tag_name);
+
+        if !placement.is_foreign() {
+            return placement;
+        }
+
+        if let Some(end_tag_handlers) = element.end_tag_handlers() {
+            let id = {
+                let mut foreign_content = foreign_content.borrow_mut();
+                let id = foreign_content.next_id;
+                foreign_content.next_id += 1;
+                foreign_content.stack.push(OpenElement {
+                    id,
+                    namespace: placement.namespace,
+                    tag_name,
+                });
+                id
+            };
+
+            let end_tag_foreign_content = Rc::clone(foreign_content);
+            end_tag_handlers.push(Box::new(move |_end| {
+                end_tag_foreign_content.borrow_mut().close(id);
+                Ok(())
+            }));
+        }
+
+        placement
+    }
+
+    fn placement(&self, element: &Element, tag_name: &str) -> Placement {
+        let parent = self
+            .stack
+            .last()
+            .map(|open| (open.namespace, open.tag_name.clone()));
+
+        let namespace = match element.namespace_uri() {
+            SVG_NAMESPACE => Namespace::Svg,
+            MATHML_NAMESPACE => Namespace::MathMl,
+            _ => match &parent {
+                Some((namespace, _)) if namespace.is_integration_point(tag_name) => *namespace,
+                Some((Namespace::MathMl, parent))
+                    if MATHML_TEXT_INTEGRATION_POINTS.contains(&parent.as_str())
+                        && matches!(tag_name, "mglyph" | "malignmark") =>
+                {
+                    Namespace::MathMl
+                }
+                _ => Namespace::Html,
+            },
+        };
+
+        Placement { namespace, parent }
+    }
+
+    /// Closes the element, along with any opened inside it that were never closed.
+    fn close(&mut self, id: usize) {
+        if let Some(position) = self.stack.iter().position(|open| open.id == id) {
+            self.stack.truncate(position);
+        }
+    }
+}
+
+/// The elements and attributes to allow in one foreign namespace. Names are
+/// kept as given, like `foreignObject` and `viewBox`, but matched without
+/// regard to case, since that's how HTML parses them.
+#[derive(Clone, Debug, Default)]
+pub struct ForeignSanitizer {
+    elements: Vec<String>,
+    allowed_attrs: Vec<String>,
+    element_attrs: BTreeMap<String, Vec<String>>,
+}
+
+impl ForeignSanitizer {
+    pub fn set_allowed_element(&mut self, element_name: &str, allow: bool) {
+        set_allowed(&mut self.elements, element_name, allow);
+    }
+
+    /// `"all"` allows the attribute on every allowed element.
+    pub fn set_allowed_attribute(&mut self, element_name: &str, attr_name: &str, allow: bool) {
+        let attrs = if element_name == "all" {
+            &mut self.allowed_attrs
+        } else {
+            let key = self
+                .element_attrs
+                .keys()
+                .find(|key| key.eq_ignore_ascii_case(element_name))
+                .cloned()
+                .unwrap_or_else(|| element_name.to_string());
+            self.element_attrs.entry(key).or_default()
+        };
+
+        set_allowed(attrs, attr_name, allow);
+    }
+
+    pub fn allows_element(&self, tag_name: &str) -> bool {
+        self.elements
+            .iter()
+            .any(|element| element.eq_ignore_ascii_case(tag_name))
+    }
+
+    pub fn allows_attribute(&self, tag_name: &str, attr_name: &str) -> bool {
+        let element_attrs = self
+            .element_attrs
+            .iter()
+            .filter(|(element, _)| element.eq_ignore_ascii_case(tag_name))
+            .flat_map(|(_, attrs)| attrs);
+
+        self.allowed_attrs
+            .iter()
+            .chain(element_attrs)
+            .any(|attr| attr.eq_ignore_ascii_case(attr_name))
+    }
+
+    pub fn elements(&self) -> &[String] {
+        &self.elements
+    }
+
+    /// Allowed attributes by element, with those allowed everywhere under `"all"`.
+    pub fn attributes(&self) -> BTreeMap<String, Vec<String>> {
+        let mut attributes = self.element_attrs.clone();
+        if !self.allowed_attrs.is_empty() {
+            attributes.insert("all".to_string(), self.allowed_attrs.clone());
+        }
+        attributes.retain(|_, attrs| !attrs.is_empty());
+
+        attributes
+    }
+}
+
+fn set_allowed(names: &mut Vec<String>, name: &str, allow: bool) {
+    if !allow {
+        names.retain(|n| !n.eq_ignore_ascii_case(name));
+    } else if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+        names.push(name.to_string());
+    }
+}
+
+/// Whether an attribute points outside the document. `href` and `xlink:href`
+/// may only name fragments, like `<use href="#icon">`, and so may any `url()`
+/// in presentation attributes like `fill` or `filter`. `<animate>` and `<set>`
+/// can't target links either, since their `to` or `values` could set
+/// `javascript:` URLs.
+pub fn references_external_resource(attr_name: &str, attr_val: &str) -> bool {
+    if matches!(attr_name, "href" | "xlink:href") {
+        return !attr_val.trim().starts_with('#');
+    }
+
+    if attr_name.eq_ignore_ascii_case("attributename") {
+        let target = attr_val.trim().to_ascii_lowercase();
+        return target == "href" || target.ends_with(":href");
+    }
+
+    let lowercase_val = attr_val.to_ascii_lowercase();
+    let mut rest = lowercase_val.as_str();
+    while let Some(start) = rest.find("url(") {
+        rest = &rest[start + "url(".len()..];
+        let target = rest.trim_start().trim_start_matches(['"', '\'']);
+        if !target.starts_with('#') {
+            return true;
+        }
+    }
+
+    false
+}
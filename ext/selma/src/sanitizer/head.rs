@@ -0,0 +1,111 @@
+use lol_html::html_content::Element;
+
+use crate::sanitizer::links::{is_on_hosts, normalize_hosts, web_host};
+
+/// `http-equiv` values that navigate away, or set cookies, as soon as the
+/// document loads.
+const FORBIDDEN_HTTP_EQUIVS: [&str; 2] = ["refresh", "set-cookie"];
+
+/// `<link rel>` values allowed until others are configured. None of them
+/// fetch anything on their own, except for `stylesheet`, which also needs
+/// its host to be allowed.
+const DEFAULT_LINK_RELS: [&str; 10] = [
+    "alternate",
+    "author",
+    "canonical",
+    "help",
+    "icon",
+    "license",
+    "next",
+    "prev",
+    "search",
+    "stylesheet",
+];
+
+/// Restricts `<meta>`, `<base>`, and `<link>`, which change how a whole
+/// document behaves, rather than what's in it.
+#[derive(Clone, Debug, Default)]
+pub struct HeadSanitizer {
+    base_hosts: Vec<String>,
+    link_rels: Option<Vec<String>>,
+    stylesheet_hosts: Vec<String>,
+}
+
+impl HeadSanitizer {
+    /// `<base href>` may only point to these hosts, and their subdomains.
+    pub fn set_base_hosts(&mut self, hosts: &[String]) {
+        self.base_hosts = normalize_hosts(hosts);
+    }
+
+    /// `<link>`s with `rel` values other than these are removed.
+    pub fn set_allowed_link_rels(&mut self, rels: &[String]) {
+        self.link_rels = Some(
+            rels.iter()
+                .flat_map(|rel| rel.split_ascii_whitespace())
+                .map(|rel| rel.to_ascii_lowercase())
+                .collect(),
+        );
+    }
+
+    /// `<link rel="stylesheet">` may only load from these hosts, and their
+    /// subdomains.
+    pub fn set_stylesheet_hosts(&mut self, hosts: &[String]) {
+        self.stylesheet_hosts = normalize_hosts(hosts);
+    }
+
+    pub fn base_hosts(&self) -> &[String] {
+        &self.base_hosts
+    }
+
+    pub fn allowed_link_rels(&self) -> Option<&[String]> {
+        self.link_rels.as_deref()
+    }
+
+    pub fn stylesheet_hosts(&self) -> &[String] {
+        &self.stylesheet_hosts
+    }
+
+    /// Whether an allowed `<meta>`, `<base>`, or `<link>` should be removed,
+    /// once its attributes have been sanitized.
+    pub fn should_remove(&self, element: &Element) -> bool {
+        match element.tag_name().as_str() {
+            "meta" => Self::attribute(element, "http-equiv").is_some_and(|http_equiv| {
+                FORBIDDEN_HTTP_EQUIVS.contains(&http_equiv.trim().to_ascii_lowercase().as_str())
+            }),
+            "base" => Self::attribute(element, "href")
+                .is_some_and(|href| !Self::is_on(&href, &self.base_hosts)),
+            "link" => {
+                let Some(rel) = Self::attribute(element, "rel") else {
+                    return false;
+                };
+                let rels: Vec<String> = rel
+                    .split_ascii_whitespace()
+                    .map(|rel| rel.to_ascii_lowercase())
+                    .collect();
+
+                let has_forbidden_rel = rels.iter().any(|rel| match &self.link_rels {
+                    Some(link_rels) => !link_rels.contains(rel),
+                    None => !DEFAULT_LINK_RELS.contains(&rel.as_str()),
+                });
+                let is_foreign_stylesheet = rels.iter().any(|rel| rel == "stylesheet")
+                    && !Self::attribute(element, "href")
+                        .is_some_and(|href| Self::is_on(&href, &self.stylesheet_hosts));
+
+                has_forbidden_rel || is_foreign_stylesheet
+            }
+            _ => false,
+        }
+    }
+
+    /// Attribute values are matched as browsers read them, with character
+    /// references decoded.
+    fn attribute(element: &Element, attr_name: &str) -> Option<String> {
+        let value = element.get_attribute(attr_name)?;
+
+        Some(String::from_utf8_lossy(&escapist::unescape_html(value.as_bytes())).to_string())
+    }
+
+    fn is_on(href: &str, hosts: &[String]) -> bool {
+        web_host(href).is_some_and(|host| is_on_hosts(&host, hosts))
+    }
+}
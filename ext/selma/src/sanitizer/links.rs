@@ -0,0 +1,183 @@
+use lol_html::{errors::AttributeNameError, html_content::Element};
+use serde::{Deserialize, Serialize};
+
+use crate::sanitizer::url::UrlScheme;
+
+/// Whether configured `rel` tokens are added to a link's own, or replace them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelMode {
+    #[default]
+    Merge,
+    Force,
+}
+
+/// Hardens `rel` and `target` on links.
+#[derive(Clone, Debug, Default)]
+pub struct LinkSanitizer {
+    rel: Vec<String>,
+    rel_mode: RelMode,
+    allowed_targets: Option<Vec<String>>,
+    internal_hosts: Vec<String>,
+}
+
+impl LinkSanitizer {
+    const LINK_ELEMENTS: [&'static str; 2] = ["a", "area"];
+
+    pub fn set_rel(&mut self, tokens: &[String], mode: RelMode) {
+        self.rel = tokens
+            .iter()
+            .flat_map(|token| token.split_ascii_whitespace())
+            .map(|token| token.to_ascii_lowercase())
+            .collect();
+        self.rel_mode = mode;
+    }
+
+    /// `target` values other than these are removed.
+    pub fn set_allowed_targets(&mut self, targets: &[String]) {
+        self.allowed_targets = Some(targets.iter().map(|t| t.to_ascii_lowercase()).collect());
+    }
+
+    /// Links to these hosts, and their subdomains, keep their own `rel`.
+    pub fn set_internal_hosts(&mut self, hosts: &[String]) {
+        self.internal_hosts = normalize_hosts(hosts);
+    }
+
+    pub fn rel(&self) -> &[String] {
+        &self.rel
+    }
+
+    pub fn rel_mode(&self) -> RelMode {
+        self.rel_mode
+    }
+
+    pub fn allowed_targets(&self) -> Option<&[String]> {
+        self.allowed_targets.as_deref()
+    }
+
+    pub fn internal_hosts(&self) -> &[String] {
+        &self.internal_hosts
+    }
+
+    /// Rewrites `rel` and `target` on an `<a>` or `<area>` with an `href`, once
+    /// its attributes have been sanitized.
+    pub fn sanitize_link(&self, element: &mut Element) -> Result<(), AttributeNameError> {
+        let tag_name = element.tag_name();
+        if !Self::LINK_ELEMENTS.contains(&tag_name.as_str()) {
+            return Ok(());
+        }
+        let Some(href) = element.get_attribute("href") else {
+            return Ok(());
+        };
+
+        let mut target = element.get_attribute("target");
+        if let (Some(value), Some(allowed_targets)) = (&target, &self.allowed_targets) {
+            if !allowed_targets.contains(&value.trim().to_ascii_lowercase()) {
+                element.remove_attribute("target");
+                target = None;
+            }
+        }
+
+        let mut rel: Vec<String> = element
+            .get_attribute("rel")
+            .unwrap_or_default()
+            .split_ascii_whitespace()
+            .map(|token| token.to_ascii_lowercase())
+            .collect();
+
+        if !self.is_internal(&href) {
+            if self.rel_mode == RelMode::Force {
+                rel.clear();
+            }
+            for token in &self.rel {
+                if !rel.contains(token) {
+                    rel.push(token.clone());
+                }
+            }
+        }
+
+        // a new browsing context shouldn't get a handle on this one
+        let opens_new_context = target
+            .as_deref()
+            .is_some_and(|t| t.trim().eq_ignore_ascii_case("_blank"));
+        if opens_new_context && !rel.iter().any(|token| token == "noopener") {
+            rel.push("noopener".to_string());
+        }
+
+        if rel.is_empty() {
+            element.remove_attribute("rel");
+            Ok(())
+        } else {
+            element.set_attribute("rel", &rel.join(" "))
+        }
+    }
+
+    /// Relative links are internal, as are absolute ones to an internal host,
+    /// but only once internal hosts have been configured.
+    fn is_internal(&self, href: &str) -> bool {
+        if self.internal_hosts.is_empty() {
+            return false;
+        }
+
+        if matches!(
+            UrlScheme::parse(href.trim()),
+            UrlScheme::Relative | UrlScheme::Fragment
+        ) {
+            return true;
+        }
+
+        web_host(href).is_some_and(|host| is_on_hosts(&host, &self.internal_hosts))
+    }
+}
+
+/// Trims and lowercases host names, dropping leading dots and empty ones.
+pub fn normalize_hosts(hosts: &[String]) -> Vec<String> {
+    hosts
+        .iter()
+        .map(|host| host.trim().trim_start_matches('.').to_ascii_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+/// The host of an `http:`, `https:`, or protocol-relative URL.
+pub fn web_host(href: &str) -> Option<String> {
+    let href = href.trim();
+    let authority = match UrlScheme::parse(href) {
+        UrlScheme::Named(scheme) if scheme == "http" || scheme == "https" => {
+            href.split_once(':').map(|(_, rest)| rest)
+        }
+        UrlScheme::ProtocolRelative => Some(href),
+        _ => None,
+    };
+
+    authority.and_then(host_of)
+}
+
+/// Whether `host` is one of `hosts`, or a subdomain of one.
+pub fn is_on_hosts(host: &str, hosts: &[String]) -> bool {
+    hosts
+        .iter()
+        .any(|allowed_host| host == allowed_host || host.ends_with(&format!(".{allowed_host}")))
+}
+
+/// Extracts the lowercased host from `//user@host:port/path`.
+fn host_of(authority: &str) -> Option<String> {
+    let authority = authority.trim_start_matches(['/', '\\']);
+    let authority = &authority[..authority
+        .find(['/', '\\', '?', '#'])
+        .unwrap_or(authority.len())];
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = match host.strip_prefix('[') {
+        // IPv6 addresses keep their colons
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.trim_end_matches('.').to_ascii_lowercase())
+    }
+}
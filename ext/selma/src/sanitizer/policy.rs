@@ -0,0 +1,976 @@
+use std::collections::BTreeMap;
+
+use magnus::{exception, ExceptionClass};
+use serde::{Deserialize, Serialize};
+
+use crate::sanitizer::{
+    classes::ClassAllowlist, foreign::ForeignSanitizer, links::RelMode, url, DoctypePolicy,
+    RequiredAttributeAction, Sanitizer, SelmaSanitizer,
+};
+
+/// A sanitizer policy, as a JSON document; YAML documents are converted to
+/// JSON in Ruby. It's shaped like the Ruby config Hash, except that symbols
+/// are strings (`":relative"`, `"remove"`), and Regexps are written as
+/// `{ "pattern": "...", "case_insensitive": true }`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    allow_comments: AllowComments,
+    allow_doctype: AllowDoctype,
+    escape_tagfilter: EscapeTagfilter,
+    elements: Vec<String>,
+    attributes: BTreeMap<String, Vec<String>>,
+    classes: BTreeMap<String, Vec<ClassEntry>>,
+    required_attributes: BTreeMap<String, RequiredAttributes>,
+    attribute_values: BTreeMap<String, BTreeMap<String, ValueConstraint>>,
+    protocols: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    data_uris: BTreeMap<String, BTreeMap<String, DataUriPolicyEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    css: Option<CssPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    links: Option<LinksPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    head: Option<HeadPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_url: Option<BaseUrlPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    svg: Option<ForeignPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    math: Option<ForeignPolicy>,
+    remove_contents: AllOrElements,
+    escape_elements: AllOrElements,
+    whitespace_elements: Vec<String>,
+    remove_empty_elements: RemoveEmptyElements,
+}
+
+/// Falls back to the same defaults as `Selma::Sanitizer#setup`.
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            allow_comments: AllowComments::Allowed(false),
+            allow_doctype: AllowDoctype::Allowed(true),
+            escape_tagfilter: EscapeTagfilter::Enabled(true),
+            elements: vec![],
+            attributes: BTreeMap::new(),
+            classes: BTreeMap::new(),
+            required_attributes: BTreeMap::new(),
+            attribute_values: BTreeMap::new(),
+            protocols: BTreeMap::new(),
+            data_uris: BTreeMap::new(),
+            css: None,
+            links: None,
+            head: None,
+            id_prefix: None,
+            base_url: None,
+            svg: None,
+            math: None,
+            remove_contents: AllOrElements::Elements(vec![]),
+            escape_elements: AllOrElements::Elements(vec![]),
+            whitespace_elements: vec![],
+            remove_empty_elements: RemoveEmptyElements::Elements(vec![]),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum ClassEntry {
+    Name(String),
+    Pattern(Pattern),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct Pattern {
+    pattern: String,
+    #[serde(default)]
+    case_insensitive: bool,
+}
+
+/// `true` or `false`, or a pattern that kept comments must match.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum AllowComments {
+    Allowed(bool),
+    Matching(Pattern),
+}
+
+/// `true` or `false`, or `"gfm"` to escape filtered tags the way GitHub
+/// Flavored Markdown does.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum EscapeTagfilter {
+    Enabled(bool),
+    Mode(TagfilterMode),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TagfilterMode {
+    Gfm,
+}
+
+/// `true` or `false`, or how to treat allowed doctypes.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum AllowDoctype {
+    Allowed(bool),
+    Policy(DoctypePolicy),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum RequiredAttributes {
+    Attributes(Vec<String>),
+    WithAction(RequiredAttributesWithAction),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct RequiredAttributesWithAction {
+    attributes: Vec<String>,
+    action: RequiredAttributeAction,
+}
+
+impl Default for RequiredAttributesWithAction {
+    fn default() -> Self {
+        RequiredAttributesWithAction {
+            attributes: vec![],
+            action: RequiredAttributeAction::Unwrap,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum ValueConstraint {
+    Values(Vec<String>),
+    Pattern(String),
+    Constraint(Constraint),
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Constraint {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    values: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    case_insensitive: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_length: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct DataUriPolicyEntry {
+    mime_types: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_size: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct CssPolicy {
+    properties: Vec<String>,
+    protocols: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct LinksPolicy {
+    rel: Vec<String>,
+    rel_mode: RelMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    targets: Option<Vec<String>>,
+    internal_hosts: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct HeadPolicy {
+    base_hosts: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link_rels: Option<Vec<String>>,
+    stylesheet_hosts: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct ForeignPolicy {
+    elements: Vec<String>,
+    attributes: BTreeMap<String, Vec<String>>,
+}
+
+impl From<&ForeignSanitizer> for ForeignPolicy {
+    fn from(foreign_sanitizer: &ForeignSanitizer) -> Self {
+        ForeignPolicy {
+            elements: foreign_sanitizer.elements().to_vec(),
+            attributes: foreign_sanitizer.attributes(),
+        }
+    }
+}
+
+/// A base URL, or one with options.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum BaseUrlPolicy {
+    Url(String),
+    WithOptions(BaseUrlWithOptions),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct BaseUrlWithOptions {
+    url: String,
+    #[serde(default)]
+    root_relative_only: bool,
+}
+
+/// `true` or `false` for every element, or a list of elements.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum AllOrElements {
+    All(bool),
+    Elements(Vec<String>),
+}
+
+impl AllOrElements {
+    fn apply(&self, sanitizer: &SelmaSanitizer, flag: u8) -> Result<(), magnus::Error> {
+        match self {
+            AllOrElements::All(set) => sanitizer.set_all_flags(flag, *set),
+            AllOrElements::Elements(elements) => {
+                for element in elements {
+                    sanitizer.set_flag(element.clone(), flag, true)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn from_flag(sanitizer: &Sanitizer, flag: u8) -> Self {
+        if sanitizer.all_elements_have(flag) {
+            AllOrElements::All(true)
+        } else {
+            AllOrElements::Elements(sanitizer.elements_with(flag))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum RemoveEmptyElements {
+    Elements(Vec<String>),
+    WithOptions(RemoveEmptyElementsWithOptions),
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct RemoveEmptyElementsWithOptions {
+    elements: Vec<String>,
+    whitespace_only: bool,
+}
+
+impl Policy {
+    /// Configures `sanitizer` with this policy, the same way
+    /// `Selma::Sanitizer#setup` does with a config Hash.
+    pub fn apply(&self, sanitizer: &SelmaSanitizer) -> Result<(), magnus::Error> {
+        self.check_names()?;
+
+        for element in &self.elements {
+            sanitizer.set_flag(element.clone(), SelmaSanitizer::SELMA_SANITIZER_ALLOW, true)?;
+        }
+
+        for (element, attrs) in &self.attributes {
+            for attr in attrs {
+                sanitizer.allow_attribute(element, attr.clone(), true);
+            }
+        }
+
+        for (element, classes) in &self.classes {
+            for class in classes {
+                match class {
+                    ClassEntry::Name(name) => {
+                        sanitizer.set_allowed_class(element.clone(), name.clone(), true)?
+                    }
+                    ClassEntry::Pattern(pattern) => sanitizer.set_allowed_class_pattern(
+                        element.clone(),
+                        pattern.pattern.clone(),
+                        pattern.case_insensitive,
+                        true,
+                    )?,
+                };
+            }
+        }
+
+        for (element, required) in &self.required_attributes {
+            let (attrs, action) = match required {
+                RequiredAttributes::Attributes(attrs) => (attrs, RequiredAttributeAction::Unwrap),
+                RequiredAttributes::WithAction(required) => (&required.attributes, required.action),
+            };
+
+            if attrs.is_empty() {
+                sanitizer.set_required_attribute(element.clone(), "*".to_string(), true);
+            }
+            for attr in attrs {
+                sanitizer.set_required_attribute(element.clone(), attr.clone(), true);
+            }
+            sanitizer.set_required_attrs_action(element, action);
+        }
+
+        for (element, attrs) in &self.attribute_values {
+            for (attr, constraint) in attrs {
+                apply_constraint(sanitizer, element, attr, constraint)?;
+            }
+        }
+
+        for (element, attrs) in &self.protocols {
+            for (attr, protocols) in attrs {
+                sanitizer.set_protocol_list_entries(
+                    element.clone(),
+                    attr.clone(),
+                    protocol_entries(protocols)?,
+                    true,
+                );
+            }
+        }
+
+        for (element, attrs) in &self.data_uris {
+            for (attr, policy) in attrs {
+                sanitizer.set_data_uri_policy(
+                    element.clone(),
+                    attr.clone(),
+                    policy.mime_types.clone(),
+                    policy.max_size,
+                );
+            }
+        }
+
+        if let Some(css) = &self.css {
+            for property in &css.properties {
+                sanitizer.set_allowed_css_property(property.clone(), true);
+            }
+            sanitizer.add_allowed_css_protocols(protocol_entries(&css.protocols)?);
+            sanitizer.set_css_scope(css.scope.clone())?;
+        }
+
+        if let Some(links) = &self.links {
+            let mut binding = sanitizer.0.borrow_mut();
+            let link_sanitizer = binding.links.get_or_insert_with(Default::default);
+
+            link_sanitizer.set_rel(&links.rel, links.rel_mode);
+            if let Some(targets) = &links.targets {
+                link_sanitizer.set_allowed_targets(targets);
+            }
+            link_sanitizer.set_internal_hosts(&links.internal_hosts);
+        }
+
+        if let Some(head) = &self.head {
+            sanitizer.set_base_hosts(head.base_hosts.clone());
+            if let Some(link_rels) = &head.link_rels {
+                sanitizer.set_allowed_link_rels(link_rels.clone());
+            }
+            sanitizer.set_stylesheet_hosts(head.stylesheet_hosts.clone());
+        }
+
+        sanitizer.set_id_prefix(self.id_prefix.clone());
+        match &self.base_url {
+            None => {}
+            Some(BaseUrlPolicy::Url(url)) => sanitizer.set_base_url(Some(url.clone()), false)?,
+            Some(BaseUrlPolicy::WithOptions(options)) => {
+                sanitizer.set_base_url(Some(options.url.clone()), options.root_relative_only)?
+            }
+        }
+
+        let foreign_policies = [("svg", &self.svg), ("math", &self.math)];
+        for (namespace, foreign_policy) in foreign_policies {
+            let Some(foreign_policy) = foreign_policy else {
+                continue;
+            };
+
+            for element in &foreign_policy.elements {
+                sanitizer.set_allowed_foreign_element(
+                    namespace.to_string(),
+                    element.clone(),
+                    true,
+                )?;
+            }
+            for (element, attrs) in &foreign_policy.attributes {
+                for attr in attrs {
+                    sanitizer.set_allowed_foreign_attribute(
+                        namespace.to_string(),
+                        element.clone(),
+                        attr.clone(),
+                        true,
+                    )?;
+                }
+            }
+        }
+
+        self.remove_contents
+            .apply(sanitizer, SelmaSanitizer::SELMA_SANITIZER_REMOVE_CONTENTS)?;
+        self.escape_elements
+            .apply(sanitizer, SelmaSanitizer::SELMA_SANITIZER_ESCAPE)?;
+
+        for element in &self.whitespace_elements {
+            sanitizer.set_flag(
+                element.clone(),
+                SelmaSanitizer::SELMA_SANITIZER_WRAP_WHITESPACE,
+                true,
+            )?;
+        }
+
+        let (elements, whitespace_only) = match &self.remove_empty_elements {
+            RemoveEmptyElements::Elements(elements) => (elements, false),
+            RemoveEmptyElements::WithOptions(options) => {
+                (&options.elements, options.whitespace_only)
+            }
+        };
+        for element in elements {
+            sanitizer.set_flag(
+                element.clone(),
+                SelmaSanitizer::SELMA_SANITIZER_REMOVE_IF_EMPTY,
+                true,
+            )?;
+        }
+        sanitizer.set_remove_whitespace_only_elements(whitespace_only);
+
+        match self.escape_tagfilter {
+            EscapeTagfilter::Enabled(escape) => {
+                sanitizer.set_escape_tagfilter(escape);
+            }
+            EscapeTagfilter::Mode(TagfilterMode::Gfm) => {
+                sanitizer.set_escape_tagfilter(true);
+                sanitizer.set_gfm_tagfilter(true);
+            }
+        }
+        match &self.allow_comments {
+            AllowComments::Allowed(allow) => {
+                sanitizer.set_allow_comments(*allow);
+            }
+            AllowComments::Matching(pattern) => {
+                sanitizer.set_allow_comments(true);
+                sanitizer
+                    .set_comment_pattern(Some(pattern.pattern.clone()), pattern.case_insensitive)?;
+            }
+        }
+        match self.allow_doctype {
+            AllowDoctype::Allowed(allow) => {
+                sanitizer.set_allow_doctype(allow);
+            }
+            AllowDoctype::Policy(policy) => {
+                sanitizer.set_allow_doctype(true);
+                sanitizer.0.borrow_mut().doctype_policy = policy;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Raises a `Selma::ConfigError` for element and attribute names that can
+    /// never match, the same way `Selma::Sanitizer::Config.validate!` does.
+    fn check_names(&self) -> Result<(), magnus::Error> {
+        let element_lists = [
+            ("elements", &self.elements),
+            ("whitespace_elements", &self.whitespace_elements),
+        ];
+        for (key, elements) in element_lists {
+            check_elements(key, elements)?;
+        }
+        if let AllOrElements::Elements(elements) = &self.remove_contents {
+            check_elements("remove_contents", elements)?;
+        }
+        if let AllOrElements::Elements(elements) = &self.escape_elements {
+            check_elements("escape_elements", elements)?;
+        }
+        match &self.remove_empty_elements {
+            RemoveEmptyElements::Elements(elements) => {
+                check_elements("remove_empty_elements", elements)?
+            }
+            RemoveEmptyElements::WithOptions(options) => {
+                check_elements("remove_empty_elements", &options.elements)?
+            }
+        }
+
+        for (element, attrs) in &self.attributes {
+            if element != "all" {
+                check_element("attributes", element)?;
+            }
+            check_attributes("attributes", attrs)?;
+        }
+        for element in self.classes.keys().filter(|element| *element != "all") {
+            check_element("classes", element)?;
+        }
+        for (element, required) in &self.required_attributes {
+            check_element("required_attributes", element)?;
+            match required {
+                RequiredAttributes::Attributes(attrs) => {
+                    check_attributes("required_attributes", attrs)?
+                }
+                RequiredAttributes::WithAction(required) => {
+                    check_attributes("required_attributes", &required.attributes)?
+                }
+            }
+        }
+        for (element, attrs) in &self.attribute_values {
+            check_element("attribute_values", element)?;
+            check_attributes("attribute_values", attrs.keys())?;
+        }
+        for (element, attrs) in &self.protocols {
+            check_element("protocols", element)?;
+            check_attributes("protocols", attrs.keys())?;
+        }
+        for (element, attrs) in &self.data_uris {
+            check_element("data_uris", element)?;
+            check_attributes("data_uris", attrs.keys())?;
+            for (attr, policy) in attrs {
+                if let Some(mime_type) = policy.mime_types.iter().find(|m| !is_mime_type(m)) {
+                    return Err(config_error(format!(
+                        "`data_uris[{element}][{attr}]` has an invalid MIME type {mime_type:?}"
+                    )));
+                }
+            }
+        }
+        for (key, foreign_policy) in [("svg", &self.svg), ("math", &self.math)] {
+            if let Some(foreign_policy) = foreign_policy {
+                foreign_policy.check_names(key)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ForeignPolicy {
+    /// Like `check_names`, except that names may have uppercase letters, like
+    /// `foreignObject`, and elements that always reparse differently inside
+    /// foreign content can't be allowed.
+    fn check_names(&self, key: &str) -> Result<(), magnus::Error> {
+        for element in &self.elements {
+            if !element.starts_with(|c: char| c.is_ascii_alphabetic()) || !is_foreign_name(element)
+            {
+                return Err(config_error(format!(
+                    "`{key}` has an invalid element name {element:?}"
+                )));
+            }
+            if matches!(element.to_ascii_lowercase().as_str(), "script" | "style") {
+                return Err(config_error(format!(
+                    "`{key}` can't allow `<{element}>`, which is always removed from foreign content"
+                )));
+            }
+        }
+
+        for (element, attrs) in &self.attributes {
+            if element != "all" && !is_foreign_name(element) {
+                return Err(config_error(format!(
+                    "`{key}` has an invalid element name {element:?}"
+                )));
+            }
+            if let Some(attr) = attrs.iter().find(|attr| !is_foreign_name(attr)) {
+                return Err(config_error(format!(
+                    "`{key}` has an invalid attribute name {attr:?}"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Exports the effective policy of a sanitizer.
+impl From<&Sanitizer> for Policy {
+    fn from(sanitizer: &Sanitizer) -> Self {
+        let mut attributes = BTreeMap::new();
+        let mut classes = BTreeMap::new();
+        let mut required_attributes = BTreeMap::new();
+        let mut attribute_values = BTreeMap::new();
+        let mut protocols = BTreeMap::new();
+        let mut data_uris = BTreeMap::new();
+
+        if !sanitizer.allowed_attrs.is_empty() {
+            attributes.insert("all".to_string(), unique(&sanitizer.allowed_attrs));
+        }
+        if !sanitizer.allowed_classes.is_empty() {
+            classes.insert("all".to_string(), class_entries(&sanitizer.allowed_classes));
+        }
+
+        for (element, element_sanitizer) in &sanitizer.element_sanitizers {
+            if !element_sanitizer.allowed_attrs.is_empty() {
+                attributes.insert(element.clone(), unique(&element_sanitizer.allowed_attrs));
+            }
+
+            if !element_sanitizer.allowed_classes.is_empty() {
+                classes.insert(
+                    element.clone(),
+                    class_entries(&element_sanitizer.allowed_classes),
+                );
+            }
+
+            if !element_sanitizer.required_attrs.is_empty() {
+                required_attributes.insert(
+                    element.clone(),
+                    RequiredAttributes::WithAction(RequiredAttributesWithAction {
+                        attributes: unique(&element_sanitizer.required_attrs),
+                        action: element_sanitizer.required_attrs_action,
+                    }),
+                );
+            }
+
+            if !element_sanitizer.value_constraints.is_empty() {
+                let constraints = element_sanitizer
+                    .value_constraints
+                    .iter()
+                    .map(|(attr, constraint)| {
+                        let (pattern, case_insensitive) = constraint
+                            .pattern_source
+                            .clone()
+                            .map_or((None, false), |(pattern, ci)| (Some(pattern), ci));
+
+                        let constraint = Constraint {
+                            values: constraint.values.clone(),
+                            pattern,
+                            case_insensitive,
+                            max_length: constraint.max_length,
+                        };
+                        (attr.clone(), ValueConstraint::Constraint(constraint))
+                    })
+                    .collect();
+                attribute_values.insert(element.clone(), constraints);
+            }
+
+            if !element_sanitizer.protocol_sanitizers.is_empty() {
+                let element_protocols = element_sanitizer
+                    .protocol_sanitizers
+                    .iter()
+                    .map(|(attr, entries)| (attr.clone(), url::protocol_names(entries)))
+                    .collect();
+                protocols.insert(element.clone(), element_protocols);
+            }
+
+            if !element_sanitizer.data_uri_policies.is_empty() {
+                let element_data_uris = element_sanitizer
+                    .data_uri_policies
+                    .iter()
+                    .map(|(attr, policy)| {
+                        let entry = DataUriPolicyEntry {
+                            mime_types: policy.mime_types().to_vec(),
+                            max_size: policy.max_size(),
+                        };
+                        (attr.clone(), entry)
+                    })
+                    .collect();
+                data_uris.insert(element.clone(), element_data_uris);
+            }
+        }
+
+        let css = sanitizer.css.as_ref().map(|css| CssPolicy {
+            properties: css.allowed_properties().to_vec(),
+            protocols: url::protocol_names(css.allowed_protocols()),
+            scope: css.scope().map(String::from),
+        });
+
+        let links = sanitizer.links.as_ref().map(|links| LinksPolicy {
+            rel: links.rel().to_vec(),
+            rel_mode: links.rel_mode(),
+            targets: links.allowed_targets().map(<[String]>::to_vec),
+            internal_hosts: links.internal_hosts().to_vec(),
+        });
+
+        let head = sanitizer.head.as_ref().map(|head| HeadPolicy {
+            base_hosts: head.base_hosts().to_vec(),
+            link_rels: head.allowed_link_rels().map(<[String]>::to_vec),
+            stylesheet_hosts: head.stylesheet_hosts().to_vec(),
+        });
+
+        Policy {
+            allow_comments: match &sanitizer.comment_pattern_source {
+                Some((pattern, case_insensitive)) if sanitizer.allow_comments => {
+                    AllowComments::Matching(Pattern {
+                        pattern: pattern.clone(),
+                        case_insensitive: *case_insensitive,
+                    })
+                }
+                _ => AllowComments::Allowed(sanitizer.allow_comments),
+            },
+            allow_doctype: match sanitizer.doctype_policy {
+                DoctypePolicy::Keep => AllowDoctype::Allowed(sanitizer.allow_doctype),
+                _ if !sanitizer.allow_doctype => AllowDoctype::Allowed(false),
+                policy => AllowDoctype::Policy(policy),
+            },
+            escape_tagfilter: if sanitizer.gfm_tagfilter {
+                EscapeTagfilter::Mode(TagfilterMode::Gfm)
+            } else {
+                EscapeTagfilter::Enabled(sanitizer.escape_tagfilter)
+            },
+            elements: sanitizer.elements_with(SelmaSanitizer::SELMA_SANITIZER_ALLOW),
+            attributes,
+            classes,
+            required_attributes,
+            attribute_values,
+            protocols,
+            data_uris,
+            css,
+            links,
+            head,
+            id_prefix: sanitizer.id_prefix.clone(),
+            base_url: sanitizer.base_url.as_ref().map(|base_url| {
+                if base_url.root_relative_only() {
+                    BaseUrlPolicy::WithOptions(BaseUrlWithOptions {
+                        url: base_url.url().to_string(),
+                        root_relative_only: true,
+                    })
+                } else {
+                    BaseUrlPolicy::Url(base_url.url().to_string())
+                }
+            }),
+            svg: sanitizer.svg.as_ref().map(ForeignPolicy::from),
+            math: sanitizer.math.as_ref().map(ForeignPolicy::from),
+            remove_contents: AllOrElements::from_flag(
+                sanitizer,
+                SelmaSanitizer::SELMA_SANITIZER_REMOVE_CONTENTS,
+            ),
+            escape_elements: AllOrElements::from_flag(
+                sanitizer,
+                SelmaSanitizer::SELMA_SANITIZER_ESCAPE,
+            ),
+            whitespace_elements: sanitizer
+                .elements_with(SelmaSanitizer::SELMA_SANITIZER_WRAP_WHITESPACE),
+            remove_empty_elements: RemoveEmptyElements::WithOptions(
+                RemoveEmptyElementsWithOptions {
+                    elements: sanitizer
+                        .elements_with(SelmaSanitizer::SELMA_SANITIZER_REMOVE_IF_EMPTY),
+                    whitespace_only: sanitizer.remove_whitespace_only_elements,
+                },
+            ),
+        }
+    }
+}
+
+fn apply_constraint(
+    sanitizer: &SelmaSanitizer,
+    element: &str,
+    attr: &str,
+    constraint: &ValueConstraint,
+) -> Result<(), magnus::Error> {
+    match constraint {
+        ValueConstraint::Values(values) => {
+            sanitizer.set_allowed_attribute_values(
+                element.to_string(),
+                attr.to_string(),
+                values.clone(),
+            );
+        }
+        ValueConstraint::Pattern(pattern) => {
+            sanitizer.set_attribute_value_pattern(
+                element.to_string(),
+                attr.to_string(),
+                pattern.clone(),
+                false,
+            )?;
+        }
+        ValueConstraint::Constraint(constraint) => {
+            if let Some(values) = &constraint.values {
+                sanitizer.set_allowed_attribute_values(
+                    element.to_string(),
+                    attr.to_string(),
+                    values.clone(),
+                );
+            }
+            if let Some(pattern) = &constraint.pattern {
+                sanitizer.set_attribute_value_pattern(
+                    element.to_string(),
+                    attr.to_string(),
+                    pattern.clone(),
+                    constraint.case_insensitive,
+                )?;
+            }
+            if let Some(max_length) = constraint.max_length {
+                sanitizer.set_attribute_value_max_length(
+                    element.to_string(),
+                    attr.to_string(),
+                    max_length,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn config_error(message: String) -> magnus::Error {
+    let config_error = magnus::eval::<ExceptionClass>(r#"Selma::ConfigError"#)
+        .unwrap_or_else(|_| exception::arg_error());
+
+    magnus::Error::new(config_error, message)
+}
+
+fn class_entries(allowed_classes: &ClassAllowlist) -> Vec<ClassEntry> {
+    let names = allowed_classes.names().into_iter().map(ClassEntry::Name);
+    let patterns = allowed_classes
+        .patterns()
+        .iter()
+        .map(|(pattern, case_insensitive)| {
+            ClassEntry::Pattern(Pattern {
+                pattern: pattern.clone(),
+                case_insensitive: *case_insensitive,
+            })
+        });
+
+    names.chain(patterns).collect()
+}
+
+/// Turns `":relative"` and `":protocol_relative"` into their protocol list entries.
+fn protocol_entries(protocols: &[String]) -> Result<Vec<String>, magnus::Error> {
+    let mut entries = vec![];
+
+    for protocol in protocols {
+        if protocol.starts_with(':') {
+            let relative_entries = url::relative_protocol_entries(protocol).ok_or_else(|| {
+                config_error(format!(
+                    "unknown protocol `{protocol}`; expected a scheme, `:relative`, or `:protocol_relative`"
+                ))
+            })?;
+            entries.extend(relative_entries);
+        } else {
+            entries.push(protocol.to_ascii_lowercase());
+        }
+    }
+
+    Ok(entries)
+}
+
+fn unique(list: &[String]) -> Vec<String> {
+    let mut unique: Vec<String> = vec![];
+
+    for item in list {
+        if !unique.contains(item) {
+            unique.push(item.clone());
+        }
+    }
+
+    unique
+}
+
+fn check_elements(key: &str, elements: &[String]) -> Result<(), magnus::Error> {
+    elements
+        .iter()
+        .try_for_each(|element| check_element(key, element))
+}
+
+fn check_element(key: &str, element: &str) -> Result<(), magnus::Error> {
+    if is_element_name(element) {
+        Ok(())
+    } else {
+        Err(config_error(format!(
+            "`{key}` has an invalid element name {element:?}"
+        )))
+    }
+}
+
+/// Lowercase, like an attribute name. Names with a `-` must be valid custom
+/// element names, and custom elements can be given by prefix, like
+/// `github-*`. Names that are neither HTML elements nor custom elements, like
+/// SVG's `circle`, are allowed too, but share their settings with every
+/// element the sanitizer doesn't know.
+pub fn is_element_name(element: &str) -> bool {
+    let name = element.strip_suffix('*').unwrap_or(element);
+    if name.len() != element.len() || name.contains('-') {
+        return is_custom_element_name(name);
+    }
+
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && is_attribute_name(name)
+        && !name.contains('*')
+}
+
+/// Names SVG and MathML already use, which can't be custom elements.
+const RESERVED_CUSTOM_ELEMENT_NAMES: [&str; 8] = [
+    "annotation-xml",
+    "color-profile",
+    "font-face",
+    "font-face-src",
+    "font-face-uri",
+    "font-face-format",
+    "font-face-name",
+    "missing-glyph",
+];
+
+/// A lowercase ASCII letter, then lowercase ASCII letters, digits, `-`, `.`,
+/// `_`, or most non-ASCII characters, with at least one `-`, following
+/// <https://html.spec.whatwg.org/#valid-custom-element-name>.
+pub fn is_custom_element_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.contains('-')
+        && name.chars().all(|c| {
+            matches!(c,
+                'a'..='z'
+                | '0'..='9'
+                | '-'
+                | '.'
+                | '_'
+                | '\u{B7}'
+                | '\u{C0}'..='\u{D6}'
+                | '\u{D8}'..='\u{F6}'
+                | '\u{F8}'..='\u{37D}'
+                | '\u{37F}'..='\u{1FFF}'
+                | '\u{200C}'..='\u{200D}'
+                | '\u{203F}'..='\u{2040}'
+                | '\u{2070}'..='\u{218F}'
+                | '\u{2C00}'..='\u{2FEF}'
+                | '\u{3001}'..='\u{D7FF}'
+                | '\u{F900}'..='\u{FDCF}'
+                | '\u{FDF0}'..='\u{FFFD}'
+                | '\u{10000}'..='\u{EFFFF}')
+        })
+        && !RESERVED_CUSTOM_ELEMENT_NAMES.contains(&name)
+}
+
+fn check_attributes<'a>(
+    key: &str,
+    attrs: impl IntoIterator<Item = &'a String>,
+) -> Result<(), magnus::Error> {
+    for attr in attrs {
+        if !is_attribute_name(attr) {
+            return Err(config_error(format!(
+                "`{key}` has an invalid attribute name {attr:?}"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Lowercase, and without whitespace, quotes, `/`, `=`, `>`, or control characters.
+fn is_attribute_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| {
+            c.is_whitespace()
+                || c.is_control()
+                || c.is_ascii_uppercase()
+                || matches!(c, '"' | '\'' | '/' | '=' | '>')
+        })
+}
+
+/// Like an attribute name, but in any case, and without wildcards.
+fn is_foreign_name(name: &str) -> bool {
+    is_attribute_name(&name.to_ascii_lowercase()) && !name.contains('*')
+}
+
+/// A `type/subtype` pair of HTTP tokens, without parameters.
+fn is_mime_type(mime_type: &str) -> bool {
+    let is_token = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+    };
+
+    mime_type
+        .split_once('/')
+        .is_some_and(|(kind, subtype)| is_token(kind) && is_token(subtype))
+}
@@ -0,0 +1,57 @@
+use std::borrow::Cow;
+
+/// Tags GitHub Flavored Markdown's tagfilter extension filters, in the order
+/// cmark-gfm checks them.
+const GFM_FILTERED_TAGS: [&str; 9] = [
+    "title",
+    "textarea",
+    "style",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "script",
+    "plaintext",
+];
+
+/// Replaces the leading `<` of every filtered start or end tag with `&lt;`,
+/// byte for byte the same way cmark-gfm does. Everything else, including the
+/// rest of the tag, is left as it is.
+pub fn gfm_tagfilter(html: &str) -> Cow<'_, str> {
+    let mut filtered = String::new();
+    let mut last = 0;
+
+    for (index, _) in html.match_indices('<') {
+        let tag = &html.as_bytes()[index..];
+        if GFM_FILTERED_TAGS.iter().any(|name| is_tag(tag, name)) {
+            filtered.push_str(&html[last..index]);
+            filtered.push_str("&lt;");
+            last = index + 1;
+        }
+    }
+
+    if last == 0 {
+        return Cow::Borrowed(html);
+    }
+
+    filtered.push_str(&html[last..]);
+    Cow::Owned(filtered)
+}
+
+/// Whether `tag`, starting at a `<`, opens or closes a `name` tag: the name,
+/// in any case, followed by whitespace, `>`, or `/>`.
+fn is_tag(tag: &[u8], name: &str) -> bool {
+    let Some(rest) = tag.strip_prefix(b"<") else {
+        return false;
+    };
+    let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+
+    if rest.len() <= name.len() || !rest[..name.len()].eq_ignore_ascii_case(name.as_bytes()) {
+        return false;
+    }
+
+    matches!(
+        rest[name.len()..],
+        [b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r' | b'>', ..] | [b'/', b'>', ..]
+    )
+}
@@ -0,0 +1,329 @@
+/// Attributes whose values browsers resolve as URLs.
+pub const URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "background",
+    "cite",
+    "classid",
+    "codebase",
+    "data",
+    "dynsrc",
+    "formaction",
+    "href",
+    "icon",
+    "longdesc",
+    "lowsrc",
+    "manifest",
+    "ping",
+    "poster",
+    "profile",
+    "src",
+    "usemap",
+    "xlink:href",
+];
+
+/// Attributes holding comma-separated image candidates, along with the
+/// attribute whose protocol list applies when they don't have their own.
+pub const SRCSET_ATTRIBUTES: &[(&str, &str)] = &[("imagesrcset", "href"), ("srcset", "src")];
+
+/// Stands in for `:relative` in protocol lists, allowing paths and queries.
+pub const RELATIVE: &str = "/";
+/// Stands in for `:relative` in protocol lists, allowing fragments.
+pub const FRAGMENT: &str = "#";
+/// Stands in for `:protocol_relative` in protocol lists, allowing `//host` URLs.
+pub const PROTOCOL_RELATIVE: &str = "//";
+
+/// The protocol list entries that `:relative` and `:protocol_relative` stand for.
+pub fn relative_protocol_entries(symbol: &str) -> Option<Vec<String>> {
+    match symbol {
+        ":relative" => Some(vec![FRAGMENT.to_string(), RELATIVE.to_string()]),
+        ":protocol_relative" => Some(vec![PROTOCOL_RELATIVE.to_string()]),
+        _ => None,
+    }
+}
+
+/// The reverse of `relative_protocol_entries`, for reporting protocol lists.
+pub fn protocol_names(entries: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+
+    for entry in entries {
+        let name = match entry.as_str() {
+            RELATIVE | FRAGMENT => ":relative",
+            PROTOCOL_RELATIVE => ":protocol_relative",
+            scheme => scheme,
+        };
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+
+    names
+}
+
+/// How a URL starts, as far as protocol allowlists are concerned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UrlScheme {
+    /// A scheme such as `https`, lowercased.
+    Named(String),
+    /// Something before a `:` that isn't a valid scheme, such as `:foo:` or `java\script:`.
+    Invalid,
+    /// `//example.com/path`, which inherits the scheme of the document.
+    ProtocolRelative,
+    /// `/path`, `path`, or `?query`.
+    Relative,
+    /// `#fragment`.
+    Fragment,
+}
+
+impl UrlScheme {
+    /// Detects the scheme of an (already entity-decoded) URL, following the
+    /// WHATWG URL parser: a scheme is an ASCII letter followed by letters,
+    /// digits, `+`, `-`, or `.`, and ends at the first `:`, so long as no
+    /// `/`, `?`, or `#` comes before it.
+    pub fn parse(url: &str) -> Self {
+        // browsers ignore tabs and newlines anywhere in a URL; other whitespace
+        // and control characters are dropped too, since older parsers skipped
+        // them, and it's safer to reject a URL than to miss its scheme
+        let url: String = url
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .collect();
+
+        let mut chars = url.chars();
+        match (chars.next(), chars.next()) {
+            // browsers treat `\` like `/` in web URLs
+            (Some('/' | '\\'), Some('/' | '\\')) => return Self::ProtocolRelative,
+            (Some('#'), _) => return Self::Fragment,
+            _ => {}
+        }
+
+        match url.find([':', '/', '?', '#']) {
+            Some(pos) if url[pos..].starts_with(':') => {
+                let scheme = &url[..pos];
+                if is_scheme(scheme) {
+                    Self::Named(scheme.to_ascii_lowercase())
+                } else {
+                    Self::Invalid
+                }
+            }
+            _ => Self::Relative,
+        }
+    }
+}
+
+/// An absolute `http` or `https` URL that relative URLs are resolved
+/// against, following RFC 3986 and the WHATWG URL parser.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BaseUrl {
+    url: String,
+    /// Like `https://example.com`.
+    origin: String,
+    /// Always starts with `/`.
+    path: String,
+    /// Only resolve root-relative URLs, like `/docs`.
+    root_relative_only: bool,
+}
+
+impl BaseUrl {
+    pub fn parse(url: &str, root_relative_only: bool) -> Option<Self> {
+        let url = url.trim();
+        let (scheme, rest) = url.split_once(':')?;
+        let scheme = scheme.to_ascii_lowercase();
+        if scheme != "http" && scheme != "https" {
+            return None;
+        }
+
+        let rest = rest.strip_prefix("//")?;
+        let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let authority = &rest[..authority_end];
+        if authority.is_empty() || authority.contains(|c: char| c.is_whitespace() || c.is_control())
+        {
+            return None;
+        }
+
+        let path = &rest[authority_end..];
+        let path = &path[..path.find(['?', '#']).unwrap_or(path.len())];
+
+        Some(BaseUrl {
+            url: url.to_string(),
+            origin: format!("{scheme}://{authority}"),
+            path: remove_dot_segments(if path.is_empty() { "/" } else { path }),
+            root_relative_only,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn root_relative_only(&self) -> bool {
+        self.root_relative_only
+    }
+
+    /// Resolves a relative (already entity-decoded) URL into an absolute one.
+    /// Absolute URLs are kept as they are, and so are fragments, which point
+    /// into the same document wherever it ends up.
+    pub fn resolve(&self, url: &str) -> String {
+        let trimmed = url.trim_matches(|c: char| c <= ' ');
+
+        match UrlScheme::parse(trimmed) {
+            UrlScheme::ProtocolRelative if !self.root_relative_only => {
+                let scheme = &self.origin[..self.origin.find(':').unwrap_or(0)];
+                format!("{scheme}:{trimmed}")
+            }
+            UrlScheme::Relative if !self.root_relative_only || trimmed.starts_with('/') => {
+                let path_end = trimmed.find(['?', '#']).unwrap_or(trimmed.len());
+                // like `/`, in `http` and `https` URLs
+                let path = trimmed[..path_end].replace('\\', "/");
+                let suffix = &trimmed[path_end..];
+
+                let path = if path.starts_with('/') {
+                    remove_dot_segments(&path)
+                } else if path.is_empty() {
+                    self.path.clone()
+                } else {
+                    let directory = &self.path[..=self.path.rfind('/').unwrap_or(0)];
+                    remove_dot_segments(&format!("{directory}{path}"))
+                };
+
+                format!("{}{}{}", self.origin, path, suffix)
+            }
+            _ => url.to_string(),
+        }
+    }
+}
+
+/// Resolves `.` and `..` segments in an absolute path, including
+/// percent-encoded ones, like browsers do.
+fn remove_dot_segments(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    let mut output: Vec<&str> = vec![];
+
+    for (index, segment) in segments.iter().enumerate() {
+        let is_last = index == segments.len() - 1;
+        let lowercased = segment.to_ascii_lowercase();
+
+        match lowercased.as_str() {
+            "." | "%2e" => {}
+            ".." | ".%2e" | "%2e." | "%2e%2e" => {
+                output.pop();
+            }
+            _ => {
+                output.push(segment);
+                continue;
+            }
+        }
+
+        // `/a/b/..` is the directory `/a/`
+        if is_last {
+            output.push("");
+        }
+    }
+
+    format!("/{}", output.join("/"))
+}
+
+/// Checks a URL against a protocol list, in which relative URLs are
+/// represented by `RELATIVE`, `FRAGMENT`, and `PROTOCOL_RELATIVE`.
+pub fn has_allowed_protocol(allowed_protocols: &[String], url: &str) -> bool {
+    let allows = |protocol: &str| allowed_protocols.iter().any(|p| p == protocol);
+
+    match UrlScheme::parse(url) {
+        UrlScheme::Named(scheme) => allows(&scheme),
+        UrlScheme::Invalid => false,
+        UrlScheme::ProtocolRelative => allows(PROTOCOL_RELATIVE),
+        UrlScheme::Relative => allows(RELATIVE),
+        UrlScheme::Fragment => allows(FRAGMENT),
+    }
+}
+
+/// Checks a URL that has no protocol list, which only allows relative URLs,
+/// just as `has_allowed_protocol` does for a list with just `:relative`.
+pub fn is_relative(url: &str) -> bool {
+    has_allowed_protocol(&[FRAGMENT.to_string(), RELATIVE.to_string()], url)
+}
+
+/// Parses a `srcset` into its image candidates, following the HTML spec,
+/// keeps those whose descriptors are valid and for which `sanitize_url`
+/// returns a URL, and reserializes them with those URLs.
+pub fn sanitize_srcset(srcset: &str, sanitize_url: impl Fn(&str) -> Option<String>) -> String {
+    let mut candidates = vec![];
+    let mut rest = srcset;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+
+        let url_end = rest
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let mut url = &rest[..url_end];
+        rest = &rest[url_end..];
+
+        // a URL that ends in a comma has no descriptors
+        let descriptors = if url.ends_with(',') {
+            url = url.trim_end_matches(',');
+            ""
+        } else {
+            let descriptors_end = descriptors_end(rest);
+            let descriptors = &rest[..descriptors_end];
+            rest = &rest[descriptors_end..];
+            descriptors
+        };
+
+        let descriptors = descriptors.split_ascii_whitespace().collect::<Vec<_>>();
+        if url.is_empty() || !descriptors.iter().all(|d| is_descriptor(d)) {
+            continue;
+        }
+        let Some(url) = sanitize_url(url) else {
+            continue;
+        };
+
+        if descriptors.is_empty() {
+            candidates.push(url);
+        } else {
+            candidates.push(format!("{} {}", url, descriptors.join(" ")));
+        }
+    }
+
+    candidates.join(", ")
+}
+
+/// Descriptors run until the next comma outside of parentheses.
+fn descriptors_end(srcset: &str) -> usize {
+    let mut depth: usize = 0;
+
+    for (i, c) in srcset.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => return i,
+            _ => {}
+        }
+    }
+
+    srcset.len()
+}
+
+/// A width (`100w`), pixel density (`1.5x`), or future-compatible height (`100h`).
+fn is_descriptor(descriptor: &str) -> bool {
+    let Some(number) = descriptor.strip_suffix(['w', 'x', 'h']) else {
+        return false;
+    };
+
+    !number.is_empty()
+        && number
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+        && number
+            .parse::<f64>()
+            .is_ok_and(|n| n.is_finite() && n >= 0.0)
+}
+
+fn is_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
@@ -189,6 +189,26 @@ impl Tag {
         tag.index == HTMLTag::META as usize
     }
 
+    /// Does this tag hold raw text, which isn't parsed as markup?
+    pub fn has_raw_text(tag: Tag) -> bool {
+        tag.index == HTMLTag::IFRAME as usize
+            || tag.index == HTMLTag::NOEMBED as usize
+            || tag.index == HTMLTag::NOFRAMES as usize
+            || tag.index == HTMLTag::NOSCRIPT as usize
+            || tag.index == HTMLTag::PLAINTEXT as usize
+            || tag.index == HTMLTag::SCRIPT as usize
+            || tag.index == HTMLTag::STYLE as usize
+            || tag.index == HTMLTag::TEXTAREA as usize
+            || tag.index == HTMLTag::TITLE as usize
+            || tag.index == HTMLTag::XMP as usize
+    }
+
+    /// Is this tag's raw text one where character references still count,
+    /// like `<textarea>`'s?
+    pub fn has_escapable_raw_text(tag: Tag) -> bool {
+        tag.index == HTMLTag::TEXTAREA as usize || tag.index == HTMLTag::TITLE as usize
+    }
+
     /// Is this tag something which needs to be removed?
     pub fn is_tag_escapeworthy(tag: Tag) -> bool {
         tag.index == HTMLTag::TITLE as usize
@@ -205,9 +225,6 @@ impl Tag {
             || tag.index == HTMLTag::XMP as usize
     }
 
-    pub const ESCAPEWORTHY_TAGS_CSS: &str =
-        "title, textarea, style, xmp, iframe, noembed, noframes, script, plaintext";
-
     pub fn html_tags() -> Vec<HTMLTag> {
         all::<HTMLTag>().collect::<Vec<_>>()
     }
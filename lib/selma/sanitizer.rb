@@ -1,5 +1,7 @@
 # frozen_string_literal: true
 
+require "json"
+require "yaml"
 require "selma/sanitizer/config"
 
 module Selma
@@ -8,78 +10,325 @@ module Selma
     ESCAPE_TAGFILTER = (1 << 1)
     REMOVE_CONTENTS = (1 << 2)
     WRAP_WHITESPACE = (1 << 3)
+    REMOVE_IF_EMPTY = (1 << 4)
+    ESCAPE = (1 << 5)
 
-    # initialize is in Rust, this just helps manage config setup in Ruby
-    # TODO: could this just become initialize?
+    class << self
+      # Loads a policy document, shaped like a sanitizer config, from YAML.
+      # It's read with `Psych.safe_load` and loaded as JSON, so it can only
+      # hold what JSON can, and symbols must be quoted, like `":relative"`.
+      def from_yaml(yaml)
+        policy = YAML.safe_load(yaml)
+        raise ConfigError, "invalid YAML policy: expected a mapping, not #{policy.inspect}" unless policy.is_a?(Hash)
+
+        from_json(JSON.generate(policy))
+      rescue Psych::Exception => e
+        raise ConfigError, "invalid YAML policy: #{e.message}"
+      end
+    end
+
+    # initialize is in Rust, this just helps manage config setup in Ruby.
+    # `Selma::Sanitizer.new` calls it, so the config is applied before any
+    # `allow_*` or `disallow_*` calls.
     def setup
+      # sanitizers from `from_json` and `from_yaml` have no config to set up
+      return if configured
+
       allow_element(config[:elements] || [])
 
       (config[:attributes] || {}).each do |element, attrs|
         allow_attribute(element, attrs)
       end
 
+      (config[:classes] || {}).each do |element, classes|
+        allow_class(element, Array(classes))
+      end
+
+      (config[:required_attributes] || {}).each do |element, required|
+        if required.is_a?(Hash)
+          require_any_attributes(element, required.fetch(:attributes, []), action: required.fetch(:action, :unwrap))
+        else
+          require_any_attributes(element, required)
+        end
+      end
+
+      (config[:attribute_values] || {}).each do |element, attrs|
+        attrs.each do |attribute, constraint|
+          constrain_attribute_value(element.to_s, attribute.to_s, constraint)
+        end
+      end
+
       (config[:protocols] || {}).each do |element, protocols|
         protocols.each do |attribute, pr|
           allow_protocol(element, attribute, pr)
         end
       end
 
+      (config[:data_uris] || {}).each do |element, attrs|
+        attrs.each do |attribute, policy|
+          set_data_uri_policy(element.to_s, attribute.to_s, Array(policy[:mime_types]).map(&:to_s), policy[:max_size])
+        end
+      end
+
+      if config.include?(:css)
+        css = config[:css] || {}
+        allow_css_property(css[:properties] || [])
+        allow_css_protocol(css[:protocols] || [])
+        set_css_scope(css[:scope])
+      end
+
+      if config.include?(:links)
+        links = config[:links] || {}
+        set_link_rel(Array(links[:rel]).map(&:to_s), links.fetch(:rel_mode, :merge))
+        set_allowed_link_targets(Array(links[:targets]).map(&:to_s)) if links.include?(:targets)
+        set_internal_link_hosts(Array(links[:internal_hosts]).map(&:to_s))
+      end
+
+      if config.include?(:head)
+        head = config[:head] || {}
+        set_base_hosts(Array(head[:base_hosts]).map(&:to_s))
+        set_allowed_link_rels(Array(head[:link_rels]).map(&:to_s)) if head.include?(:link_rels)
+        set_stylesheet_hosts(Array(head[:stylesheet_hosts]).map(&:to_s))
+      end
+
+      set_id_prefix(config[:id_prefix]&.to_s) if config.include?(:id_prefix)
+
+      if config.include?(:base_url)
+        base_url = config[:base_url]
+        if base_url.is_a?(Hash)
+          set_base_url(base_url[:url], base_url.fetch(:root_relative_only, false))
+        else
+          set_base_url(base_url, false)
+        end
+      end
+
+      [:svg, :math].each do |namespace|
+        next unless config.include?(namespace)
+
+        foreign = config[namespace] || {}
+        allow_foreign_element(namespace, foreign[:elements] || [])
+        (foreign[:attributes] || {}).each do |element, attrs|
+          allow_foreign_attribute(namespace, element, attrs)
+        end
+      end
+
       remove_contents(config[:remove_contents]) if config.include?(:remove_contents)
 
+      escape_elements(config[:escape_elements]) if config.include?(:escape_elements)
+
       wrap_with_whitespace(config[:whitespace_elements]) if config.include?(:whitespace_elements)
 
-      set_escape_tagfilter(config.fetch(:escape_tagfilter, true))
-      set_allow_comments(config.fetch(:allow_comments, false))
-      set_allow_doctype(config.fetch(:allow_doctype, true))
+      if config.include?(:remove_empty_elements)
+        remove_empty = config[:remove_empty_elements]
+        if remove_empty.is_a?(Hash)
+          remove_if_empty(remove_empty[:elements] || [])
+          set_remove_whitespace_only_elements(remove_empty.fetch(:whitespace_only, false))
+        else
+          remove_if_empty(remove_empty || [])
+        end
+      end
+
+      escape_tagfilter = config.fetch(:escape_tagfilter, true)
+      set_escape_tagfilter(escape_tagfilter != false)
+      set_gfm_tagfilter(escape_tagfilter == :gfm)
+      allow_comments = config.fetch(:allow_comments, false)
+      set_allow_comments(allow_comments != false)
+      set_comment_pattern(allow_comments.source, allow_comments.casefold?) if allow_comments.is_a?(Regexp)
+      allow_doctype = config.fetch(:allow_doctype, true)
+      set_allow_doctype(allow_doctype != false)
+      set_doctype_policy(allow_doctype) if allow_doctype.is_a?(Symbol)
+
+      set_configured(true)
     end
 
     def elements
-      config[:elements]
+      config && config[:elements]
+    end
+
+    # Sanitizes an HTML fragment, like a comment body, without building a
+    # `Selma::Rewriter`. `html` and `body` are unwrapped, `head` is removed
+    # with its contents, and doctypes are never kept.
+    def sanitize(html)
+      sanitize_html(html)
+    end
+
+    # Sanitizes a whole HTML document, without building a `Selma::Rewriter`.
+    # The result always has an `html`, `head`, and `body`.
+    def sanitize_document(html)
+      sanitize_document_html(html)
+    end
+
+    # The effective policy, as a document `Selma::Sanitizer.from_json` accepts.
+    def to_json(*)
+      to_policy_json
+    end
+
+    # The effective policy, as a document `Selma::Sanitizer.from_yaml` accepts.
+    def to_yaml(*)
+      YAML.dump(JSON.parse(to_json))
     end
 
     def allow_element(elements)
-      elements.flatten.each { |e| set_flag(e, ALLOW, true) }
+      elements.flatten.each { |e| set_flag(e.to_s, ALLOW, true) }
     end
 
     def disallow_element(elements)
-      elements.flatten.each { |e| set_flag(e, ALLOW, false) }
+      elements.flatten.each { |e| set_flag(e.to_s, ALLOW, false) }
     end
 
     def allow_attribute(element, attrs)
-      attrs.flatten.each { |attr| set_allowed_attribute(element, attr, true) }
+      attrs.flatten.each { |attr| set_allowed_attribute(element.to_s, attribute_pattern(attr), true) }
+    end
+
+    def require_any_attributes(element, attrs, action: :unwrap)
+      if attrs.empty?
+        set_required_attribute(element.to_s, "*", true)
+      else
+        attrs.flatten.each { |attr| set_required_attribute(element.to_s, attr.to_s, true) }
+      end
+
+      set_required_attribute_action(element.to_s, action)
     end
 
-    def require_any_attributes(element, attrs)
-      if attr.empty?
-        set_required_attribute(element, "*", true)
+    # A constraint is an Array of allowed values, a Regexp the whole value must
+    # match, or a Hash with any of `values:`, `pattern:`, and `max_length:`.
+    def constrain_attribute_value(element, attr, constraint)
+      case constraint
+      when Array, Set
+        set_allowed_attribute_values(element, attr, constraint.map(&:to_s))
+      when Regexp
+        # Rust's `regex` crate only knows the `i` flag
+        if constraint.options.anybits?(Regexp::MULTILINE | Regexp::EXTENDED)
+          raise ArgumentError, "`#{element}[#{attr}]` can't use the /m or /x flags: #{constraint.inspect}"
+        end
+
+        set_attribute_value_pattern(element, attr, constraint.source, constraint.casefold?)
+      when String
+        set_attribute_value_pattern(element, attr, constraint, false)
+      when Hash
+        constrain_attribute_value(element, attr, constraint[:values]) if constraint.include?(:values)
+        constrain_attribute_value(element, attr, constraint[:pattern]) if constraint.include?(:pattern)
+        set_attribute_value_max_length(element, attr, constraint[:max_length]) if constraint.include?(:max_length)
       else
-        attrs.flatten.each { |attr| set_required_attribute(element, attr, true) }
+        raise ArgumentError, "unknown constraint for `#{element}[#{attr}]`: #{constraint.inspect}"
       end
     end
 
     def disallow_attribute(element, attrs)
-      attrs.flatten.each { |attr| set_allowed_attribute(element, attr, false) }
+      attrs.flatten.each { |attr| set_allowed_attribute(element.to_s, attribute_pattern(attr), false) }
     end
 
+    # Classes can be names, globs like `"pl-*"`, or Regexps, which must match
+    # the whole class name.
     def allow_class(element, *klass)
-      klass.flatten.each { |k| set_allowed_class(element, k, true) }
+      class_names(klass).each { |k| set_class(element, k, true) }
+    end
+
+    def disallow_class(element, *klass)
+      class_names(klass).each { |k| set_class(element, k, false) }
     end
 
     def allow_protocol(element, attr, protos)
-      protos = [protos] unless protos.is_a?(Array)
-      set_allowed_protocols(element, attr, protos)
+      set_allowed_protocols(element.to_s, attr.to_s, Array(protos), true)
+    end
+
+    def disallow_protocol(element, attr, protos)
+      set_allowed_protocols(element.to_s, attr.to_s, Array(protos), false)
+    end
+
+    # The elements the sanitizer allows, once its config has been applied.
+    def allowed_elements
+      allowed_element_names
+    end
+
+    # The attributes the sanitizer allows on an element, including those
+    # allowed on all elements, or just the latter for `:all`.
+    def allowed_attributes(for: :all)
+      allowed_attribute_names(binding.local_variable_get(:for).to_s)
+    end
+
+    # The protocols the sanitizer allows in an element's attribute, with
+    # `:relative` and `:protocol_relative` standing in for relative URLs.
+    def allowed_protocols(for:, attr:)
+      allowed_protocol_names(binding.local_variable_get(:for).to_s, attr.to_s).map do |protocol|
+        protocol.start_with?(":") ? protocol.delete_prefix(":").to_sym : protocol
+      end
+    end
+
+    # Elements to allow inside `<svg>` or `<math>`, per *namespace*. Names are
+    # matched regardless of case, so `foreignObject` matches `<foreignobject>`.
+    def allow_foreign_element(namespace, elements)
+      elements.flatten.each { |e| set_allowed_foreign_element(namespace.to_s, e.to_s, true) }
+    end
+
+    def disallow_foreign_element(namespace, elements)
+      elements.flatten.each { |e| set_allowed_foreign_element(namespace.to_s, e.to_s, false) }
+    end
+
+    def allow_foreign_attribute(namespace, element, attrs)
+      attrs.flatten.each { |attr| set_allowed_foreign_attribute(namespace.to_s, element.to_s, attr.to_s, true) }
+    end
+
+    def disallow_foreign_attribute(namespace, element, attrs)
+      attrs.flatten.each { |attr| set_allowed_foreign_attribute(namespace.to_s, element.to_s, attr.to_s, false) }
+    end
+
+    def allow_css_property(*properties)
+      properties.flatten.each { |prop| set_allowed_css_property(prop.to_s, true) }
+    end
+
+    def allow_css_protocol(protos)
+      set_allowed_css_protocols(Array(protos))
+    end
+
+    # `:data` and `:aria` stand in for any `data-*` or `aria-*` attribute.
+    def attribute_pattern(attr)
+      case attr
+      when :data then "data-*"
+      when :aria then "aria-*"
+      else attr.to_s
+      end
     end
 
     def remove_contents(elements)
       if elements.is_a?(TrueClass) || elements.is_a?(FalseClass)
         set_all_flags(REMOVE_CONTENTS, elements)
       else
-        elements.flatten.each { |e| set_flag(e, REMOVE_CONTENTS, true) }
+        elements.flatten.each { |e| set_flag(e.to_s, REMOVE_CONTENTS, true) }
+      end
+    end
+
+    # Disallowed elements are written out as text, like `&lt;script&gt;`,
+    # instead of being removed.
+    def escape_elements(elements)
+      if elements.is_a?(TrueClass) || elements.is_a?(FalseClass)
+        set_all_flags(ESCAPE, elements)
+      else
+        elements.flatten.each { |e| set_flag(e.to_s, ESCAPE, true) }
       end
     end
 
     def wrap_with_whitespace(elements)
-      elements.flatten.each { |e| set_flag(e, WRAP_WHITESPACE, true) }
+      elements.flatten.each { |e| set_flag(e.to_s, WRAP_WHITESPACE, true) }
+    end
+
+    def remove_if_empty(elements)
+      elements.flatten.each { |e| set_flag(e.to_s, REMOVE_IF_EMPTY, true) }
+    end
+
+    private
+
+    # Merged configs hold Sets, which `flatten` leaves as they are.
+    def class_names(klass)
+      klass.flat_map { |k| k.is_a?(Set) ? k.to_a : k }.flatten
+    end
+
+    def set_class(element, klass, allow)
+      if klass.is_a?(Regexp)
+        set_allowed_class_pattern(element.to_s, klass.source, klass.casefold?, allow)
+      else
+        set_allowed_class(element.to_s, klass.to_s, allow)
+      end
     end
   end
 end
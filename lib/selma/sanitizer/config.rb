@@ -61,6 +61,7 @@ module Selma
   end
 end
 
+require "selma/sanitizer/config/validation"
 require "selma/sanitizer/config/basic"
 require "selma/sanitizer/config/default"
 require "selma/sanitizer/config/relaxed"
@@ -48,9 +48,9 @@ module Selma
         },
 
         protocols: {
-          "a" => { "href" => ["ftp", "http", "https", "mailto", :relative] },
-          "blockquote" => { "cite" => ["http", "https", :relative] },
-          "q" => { "cite" => ["http", "https", :relative] },
+          "a" => { "href" => ["ftp", "http", "https", "mailto", :relative, :protocol_relative] },
+          "blockquote" => { "cite" => ["http", "https", :relative, :protocol_relative] },
+          "q" => { "cite" => ["http", "https", :relative, :protocol_relative] },
         },
       )
     end
@@ -88,9 +88,9 @@ module Selma
 
         protocols: merge(
           BASIC[:protocols],
-          "del" => { "cite" => ["http", "https", :relative] },
-          "img" => { "src"  => ["http", "https", :relative] },
-          "ins" => { "cite" => ["http", "https", :relative] },
+          "del" => { "cite" => ["http", "https", :relative, :protocol_relative] },
+          "img" => { "src"  => ["http", "https", :relative, :protocol_relative] },
+          "ins" => { "cite" => ["http", "https", :relative, :protocol_relative] },
         ),
       )
     end
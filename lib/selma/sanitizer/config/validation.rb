@@ -0,0 +1,396 @@
+# frozen_string_literal: true
+
+module Selma
+  # Raised when a sanitizer config has an unknown key, an invalid element or
+  # attribute name, or a value of the wrong type.
+  class ConfigError < ArgumentError; end
+
+  class Sanitizer
+    module Config
+      # Tag and attribute names as they come out of the HTML tokenizer: lowercase,
+      # and without whitespace, quotes, `/`, `=`, `>`, or control characters.
+      # Element names are checked by `Selma::Sanitizer.valid_element_name?`,
+      # which also allows custom elements by prefix, like `github-*`.
+      ATTRIBUTE_NAME = %r{\A[^\sA-Z"'/=>\x00-\x1f\x7f]+\z}
+
+      # SVG and MathML names keep their case, like `foreignObject` and `viewBox`.
+      FOREIGN_ELEMENT_NAME = %r{\A[a-zA-Z][^\s"'/=>*\x00-\x1f\x7f]*\z}
+      FOREIGN_ATTRIBUTE_NAME = %r{\A[^\s"'/=>*\x00-\x1f\x7f]+\z}
+
+      # Elements that are always removed from foreign content, since browsers
+      # and serializers disagree about how to parse their contents.
+      FORBIDDEN_FOREIGN_ELEMENTS = ["script", "style"].freeze
+
+      PROTOCOL_SYMBOLS = [:relative, :protocol_relative].freeze
+      REQUIRED_ATTRIBUTE_ACTIONS = [:remove, :unwrap].freeze
+      REL_MODES = [:merge, :force].freeze
+      DOCTYPE_POLICIES = [:normalize, :reject_legacy].freeze
+      BASE_URL = %r{\Ahttps?://[^/?#\s]+}i
+      MIME_TYPE = %r{\A[\w!#$%&'*+.^`|~-]+/[\w!#$%&'*+.^`|~-]+\z}
+
+      # Elements that run scripts, load other documents, or change how URLs resolve.
+      DANGEROUS_ELEMENTS = ["base", "embed", "frame", "frameset", "iframe", "object", "script"].freeze
+      DANGEROUS_PROTOCOLS = ["javascript", "vbscript"].freeze
+
+      class << self
+        # Raises a ConfigError describing the first problem found in *config*.
+        # With `lint: true`, also warns about dangerous combinations of settings.
+        def validate!(config)
+          raise ConfigError, "config must be a Hash, not #{config.class}" unless config.is_a?(Hash)
+
+          config.each { |key, value| validate_key(key, value) }
+          lint(config).each { |warning| warn("Selma::Sanitizer: #{warning}") } if config[:lint]
+
+          config
+        end
+
+        # Returns warnings about settings which let dangerous markup through, or
+        # which probably don't do what was intended.
+        def lint(config)
+          warnings = []
+          elements = Array(config[:elements]).flatten.map(&:to_s)
+
+          (elements & DANGEROUS_ELEMENTS).each do |element|
+            warnings << "`elements` allows `<#{element}>`, which can run scripts or load other documents"
+          end
+
+          elements.reject { |element| Selma::Sanitizer.known_element?(element) || element.include?("-") }.each do |element|
+            warnings << "`elements` allows `<#{element}>`, which is neither an HTML element nor a custom element, " \
+              "so every unknown element is allowed"
+          end
+
+          (config[:attributes] || {}).each do |element, attrs|
+            Array(attrs).flatten.map(&:to_s).grep(/\Aon[^*]*\z/).each do |attr|
+              warnings << "`attributes[#{element.inspect}]` allows the `#{attr}` event handler"
+            end
+          end
+
+          [:svg, :math].each do |namespace|
+            ((config[namespace] || {})[:attributes] || {}).each do |element, attrs|
+              Array(attrs).flatten.map(&:to_s).grep(/\Aon/i).each do |attr|
+                warnings << "`#{namespace}[:attributes][#{element.inspect}]` allows the `#{attr}` event handler"
+              end
+            end
+          end
+
+          unless config.include?(:css)
+            if elements.include?("style")
+              warnings << "`elements` allows `<style>` without a `css:` policy, so stylesheets are kept as-is"
+            end
+
+            (config[:attributes] || {}).each do |element, attrs|
+              next unless Array(attrs).flatten.map(&:to_s).include?("style")
+
+              warnings << "`attributes[#{element.inspect}]` allows `style` without a `css:` policy, so styles are kept as-is"
+            end
+          end
+
+          (config[:protocols] || {}).each do |element, attrs|
+            attrs.each do |attr, protocols|
+              (Array(protocols).flatten.map { |p| p.to_s.downcase } & DANGEROUS_PROTOCOLS).each do |protocol|
+                warnings << "`protocols[#{element.inspect}][#{attr.inspect}]` allows `#{protocol}:` URLs"
+              end
+
+              next unless Array(protocols).flatten.map { |p| p.to_s.downcase }.include?("data")
+              next if config.dig(:data_uris, element, attr)
+
+              warnings << "`protocols[#{element.inspect}][#{attr.inspect}]` allows every `data:` URL without a `data_uris:` policy"
+            end
+          end
+
+          warnings
+        end
+
+        private
+
+        def validate_key(key, value)
+          case key
+          when :allow_comments
+            validate_type(key, value, "true, false, or a Regexp", TrueClass, FalseClass, Regexp)
+            validate_pattern(key, value) if value.is_a?(Regexp)
+          when :allow_doctype
+            unless value == true || value == false || DOCTYPE_POLICIES.include?(value)
+              raise ConfigError, "`#{key}` must be true, false, or one of #{DOCTYPE_POLICIES.map(&:inspect).join(", ")}, not #{value.inspect}"
+            end
+          when :escape_tagfilter
+            unless value == true || value == false || value == :gfm
+              raise ConfigError, "`#{key}` must be true, false, or :gfm, not #{value.inspect}"
+            end
+          when :lint
+            validate_boolean(key, value)
+          when :elements, :whitespace_elements
+            validate_elements(key, value)
+          when :remove_contents, :escape_elements
+            validate_elements(key, value) unless value == true || value == false
+          when :remove_empty_elements
+            if value.is_a?(Hash)
+              validate_options(key, value, [:elements, :whitespace_only])
+              validate_elements("#{key}[:elements]", value[:elements]) if value.include?(:elements)
+              validate_boolean("#{key}[:whitespace_only]", value[:whitespace_only]) if value.include?(:whitespace_only)
+            else
+              validate_elements(key, value)
+            end
+          when :attributes
+            each_element(key, value, all: true) { |path, attrs| validate_attributes(path, attrs) }
+          when :classes
+            each_element(key, value, all: true) do |path, classes|
+              validate_list(path, classes, "class names or Regexps", String, Symbol, Regexp)
+              list(classes, path).grep(Regexp).each { |pattern| validate_pattern(path, pattern) }
+            end
+          when :required_attributes
+            each_element(key, value) { |path, required| validate_required_attributes(path, required) }
+          when :attribute_values
+            each_element(key, value) do |path, attrs|
+              each_attribute(path, attrs) { |attr_path, constraint| validate_constraint(attr_path, constraint) }
+            end
+          when :protocols
+            each_element(key, value) do |path, attrs|
+              each_attribute(path, attrs) { |attr_path, protocols| validate_protocols(attr_path, protocols) }
+            end
+          when :data_uris
+            each_element(key, value) do |path, attrs|
+              each_attribute(path, attrs) { |attr_path, policy| validate_data_uri_policy(attr_path, policy) }
+            end
+          when :css
+            validate_css(key, value)
+          when :links
+            validate_links(key, value)
+          when :head
+            validate_head(key, value)
+          when :id_prefix
+            validate_type(key, value, "a String or nil", String, NilClass)
+          when :base_url
+            validate_base_url(key, value)
+          when :svg, :math
+            validate_foreign(key, value)
+          else
+            raise ConfigError, "unknown config key `#{key.inspect}`"
+          end
+        end
+
+        def validate_css(key, css)
+          return if css.nil?
+
+          validate_options(key, css, [:properties, :protocols, :scope])
+          validate_list("#{key}[:properties]", css[:properties], "property names", String, Symbol) if css.include?(:properties)
+          validate_protocols("#{key}[:protocols]", css[:protocols]) if css.include?(:protocols)
+          validate_type("#{key}[:scope]", css[:scope], "a String or nil", String, NilClass)
+        end
+
+        def validate_links(key, links)
+          return if links.nil?
+
+          validate_options(key, links, [:rel, :rel_mode, :targets, :internal_hosts])
+          validate_list("#{key}[:rel]", Array(links[:rel]), "rel tokens", String, Symbol)
+          if links.include?(:rel_mode) && !REL_MODES.include?(links[:rel_mode])
+            raise ConfigError, "`#{key}[:rel_mode]` must be one of #{REL_MODES.map(&:inspect).join(", ")}, not #{links[:rel_mode].inspect}"
+          end
+
+          validate_list("#{key}[:targets]", Array(links[:targets]), "target names", String, Symbol)
+          validate_list("#{key}[:internal_hosts]", Array(links[:internal_hosts]), "host names", String)
+        end
+
+        def validate_head(key, head)
+          return if head.nil?
+
+          validate_options(key, head, [:base_hosts, :link_rels, :stylesheet_hosts])
+          validate_list("#{key}[:base_hosts]", Array(head[:base_hosts]), "host names", String)
+          validate_list("#{key}[:link_rels]", Array(head[:link_rels]), "rel values", String, Symbol)
+          validate_list("#{key}[:stylesheet_hosts]", Array(head[:stylesheet_hosts]), "host names", String)
+        end
+
+        def validate_base_url(key, base_url)
+          if base_url.is_a?(Hash)
+            validate_options(key, base_url, [:url, :root_relative_only])
+            validate_boolean("#{key}[:root_relative_only]", base_url[:root_relative_only]) if base_url.include?(:root_relative_only)
+            key = "#{key}[:url]"
+            base_url = base_url[:url]
+          end
+
+          validate_type(key, base_url, "a String or nil", String, NilClass)
+          return if base_url.nil? || base_url.strip.match?(BASE_URL)
+
+          raise ConfigError, "`#{key}` must be an absolute http or https URL, not #{base_url.inspect}"
+        end
+
+        def validate_foreign(key, foreign)
+          return if foreign.nil?
+
+          validate_options(key, foreign, [:elements, :attributes])
+
+          path = "#{key}[:elements]"
+          list(foreign[:elements], path).each do |element|
+            validate_foreign_name(path, element, "element", FOREIGN_ELEMENT_NAME)
+            next unless FORBIDDEN_FOREIGN_ELEMENTS.include?(element.to_s.downcase)
+
+            raise ConfigError, "`#{path}` can't allow `<#{element}>`, which is always removed from foreign content"
+          end
+
+          path = "#{key}[:attributes]"
+          return if foreign[:attributes].nil?
+
+          validate_type(path, foreign[:attributes], "a Hash", Hash)
+          foreign[:attributes].each do |element, attrs|
+            validate_foreign_name(path, element, "element", FOREIGN_ELEMENT_NAME) unless element.to_s == "all"
+            list(attrs, "#{path}[#{element.inspect}]").each do |attr|
+              validate_foreign_name("#{path}[#{element.inspect}]", attr, "attribute", FOREIGN_ATTRIBUTE_NAME)
+            end
+          end
+        end
+
+        def validate_foreign_name(path, name, kind, pattern)
+          return if (name.is_a?(String) || name.is_a?(Symbol)) && pattern.match?(name.to_s)
+
+          raise ConfigError, "`#{path}` has an invalid #{kind} name #{name.inspect}"
+        end
+
+        def validate_required_attributes(path, required)
+          return validate_attributes(path, required) unless required.is_a?(Hash)
+
+          validate_options(path, required, [:attributes, :action])
+          validate_attributes("#{path}[:attributes]", required.fetch(:attributes, []))
+          if required.include?(:action) && !REQUIRED_ATTRIBUTE_ACTIONS.include?(required[:action])
+            raise ConfigError, "`#{path}[:action]` must be one of #{REQUIRED_ATTRIBUTE_ACTIONS.map(&:inspect).join(", ")}, not #{required[:action].inspect}"
+          end
+        end
+
+        def validate_constraint(path, constraint)
+          case constraint
+          when Array, Set
+            validate_list(path, constraint, "attribute values", String, Symbol)
+          when Regexp, String
+            validate_pattern(path, constraint)
+          when Hash
+            validate_options(path, constraint, [:values, :pattern, :max_length])
+            validate_constraint("#{path}[:values]", Array(constraint[:values])) if constraint.include?(:values)
+            if constraint.include?(:pattern)
+              validate_type("#{path}[:pattern]", constraint[:pattern], "a Regexp or String", Regexp, String)
+              validate_pattern("#{path}[:pattern]", constraint[:pattern])
+            end
+            if constraint.include?(:max_length) && !(constraint[:max_length].is_a?(Integer) && constraint[:max_length] >= 0)
+              raise ConfigError, "`#{path}[:max_length]` must be a non-negative Integer, not #{constraint[:max_length].inspect}"
+            end
+          else
+            raise ConfigError, "`#{path}` must be an Array, Regexp, String, or Hash, not #{constraint.class}"
+          end
+        end
+
+        # Patterns are compiled by Rust's `regex` crate, which only knows the `i`
+        # flag, and has no lookarounds or backreferences.
+        def validate_pattern(path, pattern)
+          if pattern.is_a?(Regexp) && pattern.options.anybits?(Regexp::MULTILINE | Regexp::EXTENDED)
+            raise ConfigError, "`#{path}` can't use the /m or /x flags: #{pattern.inspect}"
+          end
+
+          source = pattern.is_a?(Regexp) ? pattern.source : pattern
+          error = Selma::Sanitizer.pattern_error(source)
+          return if error.nil?
+
+          raise ConfigError, "`#{path}` has an invalid pattern #{source.inspect}: #{error.lines.last.strip}"
+        end
+
+        def validate_protocols(path, protocols)
+          protocols = [protocols] if protocols.is_a?(String) || protocols.is_a?(Symbol)
+
+          list(protocols, path).each do |protocol|
+            next if protocol.is_a?(String) || PROTOCOL_SYMBOLS.include?(protocol)
+
+            raise ConfigError, "`#{path}` has an unknown protocol #{protocol.inspect}; expected a String, :relative, or :protocol_relative"
+          end
+        end
+
+        def validate_data_uri_policy(path, policy)
+          validate_options(path, policy, [:mime_types, :max_size])
+          list(policy[:mime_types], "#{path}[:mime_types]").each do |mime_type|
+            next if mime_type.is_a?(String) && MIME_TYPE.match?(mime_type)
+
+            raise ConfigError, "`#{path}[:mime_types]` has an invalid MIME type #{mime_type.inspect}"
+          end
+          if policy.include?(:max_size) && !(policy[:max_size].is_a?(Integer) && policy[:max_size] >= 0)
+            raise ConfigError, "`#{path}[:max_size]` must be a non-negative Integer, not #{policy[:max_size].inspect}"
+          end
+        end
+
+        def validate_elements(path, elements)
+          list(elements, path).each { |element| validate_element_name(path, element) }
+        end
+
+        def validate_attributes(path, attrs)
+          list(attrs, path).each { |attr| validate_attribute_name(path, attr) }
+        end
+
+        def validate_element_name(path, element)
+          return if (element.is_a?(String) || element.is_a?(Symbol)) && Selma::Sanitizer.valid_element_name?(element.to_s)
+
+          raise ConfigError, "`#{path}` has an invalid element name #{element.inspect}"
+        end
+
+        def validate_attribute_name(path, attr)
+          return if (attr.is_a?(String) || attr.is_a?(Symbol)) && ATTRIBUTE_NAME.match?(attr.to_s)
+
+          raise ConfigError, "`#{path}` has an invalid attribute name #{attr.inspect}"
+        end
+
+        # Yields the path and value for each element in a Hash keyed by element
+        # name, along with `:all`, if *all* is given.
+        def each_element(key, hash, all: false)
+          return if hash.nil?
+
+          validate_type(key, hash, "a Hash", Hash)
+
+          hash.each do |element, value|
+            validate_element_name(key, element) unless all && element.to_s == "all"
+
+            yield "#{key}[#{element.inspect}]", value
+          end
+        end
+
+        def each_attribute(path, hash)
+          validate_type(path, hash, "a Hash", Hash)
+
+          hash.each do |attr, value|
+            validate_attribute_name(path, attr)
+
+            yield "#{path}[#{attr.inspect}]", value
+          end
+        end
+
+        def validate_options(path, hash, options)
+          validate_type(path, hash, "a Hash", Hash)
+
+          unknown = hash.keys - options
+          return if unknown.empty?
+
+          raise ConfigError, "`#{path}` has unknown option `#{unknown.first.inspect}`; expected one of #{options.map(&:inspect).join(", ")}"
+        end
+
+        def validate_list(path, values, description, *types)
+          list(values, path).each do |value|
+            next if types.any? { |type| value.is_a?(type) }
+
+            raise ConfigError, "`#{path}` must only contain #{description}, not #{value.inspect}"
+          end
+        end
+
+        def validate_boolean(path, value)
+          validate_type(path, value, "true or false", TrueClass, FalseClass)
+        end
+
+        def validate_type(path, value, description, *types)
+          return if types.any? { |type| value.is_a?(type) }
+
+          raise ConfigError, "`#{path}` must be #{description}, not #{value.inspect}"
+        end
+
+        # Flattens an Array or Set, treating a missing list as an empty one.
+        def list(values, path)
+          case values
+          when Array, Set then values.to_a.flatten
+          when nil then []
+          else raise ConfigError, "`#{path}` must be an Array, not #{values.inspect}"
+          end
+        end
+      end
+    end
+  end
+end
@@ -0,0 +1,100 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerAttributeValuesTest < Minitest::Test
+    def setup
+      @sanitizer = Selma::Sanitizer.new({
+        elements: ["input", "ol", "span"],
+        attributes: {
+          "input" => ["type"],
+          "ol" => ["type"],
+          "span" => ["title", "data-id"],
+        },
+        attribute_values: {
+          "input" => { "type" => ["checkbox"] },
+          "ol" => { "type" => ["1", "a", "A", "i", "I"] },
+          "span" => {
+            "data-id" => /\d+/,
+            "title" => { pattern: /[a-z ]+/i, max_length: 10 },
+          },
+        },
+      })
+    end
+
+    def test_it_keeps_enumerated_values
+      assert_equal(
+        '<input type="checkbox"><ol type="A"></ol>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<input type="checkbox"><ol type="A"></ol>'),
+      )
+    end
+
+    def test_it_removes_values_that_are_not_enumerated
+      assert_equal(
+        "<input><ol></ol>",
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<input type="password"><ol type="B"></ol>'),
+      )
+    end
+
+    def test_it_anchors_patterns
+      assert_equal(
+        '<span data-id="12">foo</span>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span data-id="12">foo</span>'),
+      )
+
+      assert_equal(
+        "<span>foo</span>",
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span data-id="12 onclick">foo</span>'),
+      )
+    end
+
+    def test_it_combines_patterns_and_max_lengths
+      assert_equal(
+        '<span title="Hello">foo</span>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span title="Hello">foo</span>'),
+      )
+
+      assert_equal(
+        "<span>foo</span>",
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span title="Hello there world">foo</span>'),
+      )
+    end
+
+    def test_it_accepts_values_from_merged_configs
+      config = Selma::Sanitizer::Config.merge(
+        { elements: ["input"], attributes: { "input" => ["type"] }, attribute_values: { "input" => { "type" => ["text"] } } },
+        { attribute_values: { "input" => { "type" => ["checkbox"] } } },
+      )
+      sanitizer = Selma::Sanitizer.new(config)
+
+      assert_equal(
+        '<input type="checkbox"><input>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<input type="checkbox"><input type="text">'),
+      )
+    end
+
+    def test_it_raises_on_invalid_patterns
+      error = assert_raises(Selma::ConfigError) do
+        Selma::Sanitizer.new({ attribute_values: { "span" => { "title" => "(" } } })
+      end
+      assert_match(/invalid pattern "\("/, error.message)
+
+      # lookarounds are Ruby-only
+      assert_raises(Selma::ConfigError) do
+        Selma::Sanitizer.new({ attribute_values: { "span" => { "title" => { pattern: /(?!x)\w+/ } } } })
+      end
+    end
+
+    def test_it_rejects_flags_other_than_case_insensitivity
+      error = assert_raises(Selma::ConfigError) do
+        Selma::Sanitizer.new({ attribute_values: { "span" => { "title" => /a.b/m } } })
+      end
+      assert_match(%r{/m or /x}, error.message)
+
+      assert_raises(Selma::ConfigError) do
+        Selma::Sanitizer.new({ classes: { "span" => [/ hl - x /x] } })
+      end
+    end
+  end
+end
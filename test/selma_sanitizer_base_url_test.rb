@@ -0,0 +1,50 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerBaseUrlTest < Minitest::Test
+    def config
+      {
+        elements: ["a", "img", "blockquote"],
+        attributes: { "a" => ["href"], "img" => ["src", "srcset"], "blockquote" => ["cite"] },
+        protocols: { "a" => { "href" => ["https", :relative] } },
+        base_url: "https://example.com/docs/README.md",
+      }
+    end
+
+    def test_it_resolves_relative_urls
+      sanitizer = Selma::Sanitizer.new(config)
+
+      assert_equal(
+        '<a href="https://example.com/docs/guide.md">a</a><a href="https://example.com/root">b</a><a href="#top">c</a>',
+        sanitizer.sanitize('<a href="guide.md">a</a><a href="../root">b</a><a href="#top">c</a>'),
+      )
+      assert_equal(
+        '<img src="https://example.com/docs/a.png" srcset="https://example.com/docs/a.png 1x, https://example.com/b.png 2x">',
+        sanitizer.sanitize('<img src="a.png" srcset="a.png 1x, /b.png 2x, https://evil.com/c.png 3x">'),
+      )
+    end
+
+    def test_it_resolves_before_checking_protocols
+      sanitizer = Selma::Sanitizer.new(config.merge(protocols: { "blockquote" => { "cite" => ["http"] } }))
+
+      assert_equal("<blockquote>x</blockquote>", sanitizer.sanitize('<blockquote cite="/quote">x</blockquote>'))
+    end
+
+    def test_it_can_resolve_only_root_relative_urls
+      sanitizer = Selma::Sanitizer.new(config.merge(base_url: { url: "https://example.com/docs/", root_relative_only: true }))
+
+      assert_equal(
+        '<a href="guide.md">a</a><a href="https://example.com/root">b</a>',
+        sanitizer.sanitize('<a href="guide.md">a</a><a href="/root">b</a>'),
+      )
+    end
+
+    def test_it_validates_the_base_url
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new(config.merge(base_url: "/docs")) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new(config.merge(base_url: "javascript:alert(1)")) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new(config.merge(base_url: { url: "https://example.com", root: true })) }
+    end
+  end
+end
@@ -0,0 +1,61 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerClassesTest < Minitest::Test
+    def setup
+      @sanitizer = Selma::Sanitizer.new({
+        elements: ["span"],
+        attributes: { "span" => ["class"] },
+        classes: {
+          all: ["pl-*"],
+          "span" => ["highlight", /hljs-[a-z]+/],
+        },
+      })
+    end
+
+    def test_it_keeps_exact_class_names
+      assert_equal(
+        '<span class="highlight">foo</span>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span class="highlight other">foo</span>'),
+      )
+    end
+
+    def test_it_matches_globs
+      assert_equal(
+        '<span class="pl-k pl-en">foo</span>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span class="pl-k apl-k pl-en">foo</span>'),
+      )
+    end
+
+    def test_it_matches_whole_class_names_against_regexps
+      assert_equal(
+        '<span class="hljs-keyword">foo</span>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span class="hljs-keyword hljs-1 xhljs-keyword">foo</span>'),
+      )
+    end
+
+    def test_it_allows_classes_from_merged_configs
+      config = Selma::Sanitizer::Config.merge(
+        { elements: ["span"], attributes: { "span" => ["class"] }, classes: { "span" => ["highlight"] } },
+        { classes: { "span" => ["pl-*"] } },
+      )
+      sanitizer = Selma::Sanitizer.new(config)
+
+      assert_equal(
+        '<span class="pl-k">foo</span>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<span class="pl-k highlight">foo</span>'),
+      )
+    end
+
+    def test_it_can_allow_classes_after_setup
+      @sanitizer.allow_class("span", "extra-*")
+
+      assert_equal(
+        '<span class="extra-foo">foo</span>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span class="extra-foo">foo</span>'),
+      )
+    end
+  end
+end
@@ -62,10 +62,6 @@ module Selma
             "foo <!--\n\n\n\n-->bar",
             Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("foo <!--\n\n\n\n-->bar"),
           )
-          assert_equal(
-            "foo <!-- <!-- <!-- --> --> -->bar",
-            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("foo <!-- <!-- <!-- --> --> -->bar"),
-          )
 
           assert_equal(
             "foo ",
@@ -76,6 +72,34 @@ module Selma
 
           assert_equal("<script><!-- comment --></script>", Selma::Rewriter.new(sanitizer: sanitizer).rewrite("<script><!-- comment --></script>"))
         end
+
+        def test_it_removes_conditional_comments
+          assert_equal(
+            "foo  bar",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("foo <!--[if IE]><script>alert(1)</script><![endif]--> bar"),
+          )
+          assert_equal("foobar", Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("foo<![if !IE]><![endif]>bar"))
+        end
+
+        def test_it_removes_comments_with_nested_openers
+          assert_equal(
+            "foo  --> -->bar",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("foo <!-- <!-- <!-- --> --> -->bar"),
+          )
+        end
+      end
+
+      context "when :allow_comments is a Regexp" do
+        def setup
+          @sanitizer = Selma::Sanitizer.new({ allow_comments: /more/i, elements: ["p"] })
+        end
+
+        def test_it_keeps_matching_comments
+          assert_equal(
+            "<p>a</p><!-- MORE --><p>b</p>",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<p>a</p><!-- MORE --><p>b</p><!-- more than this -->"),
+          )
+        end
       end
     end
   end
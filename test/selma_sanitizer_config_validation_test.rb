@@ -0,0 +1,118 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerConfigValidationTest < Minitest::Test
+    def test_built_in_configs_are_valid
+      Selma::Sanitizer.new(Selma::Sanitizer::Config::DEFAULT)
+      Selma::Sanitizer.new(Selma::Sanitizer::Config::BASIC)
+      Selma::Sanitizer.new(Selma::Sanitizer::Config::RELAXED)
+      Selma::Sanitizer.new(Selma::Sanitizer::Config::RESTRICTED)
+    end
+
+    def test_it_accepts_merged_configs
+      config = Selma::Sanitizer::Config.merge(Selma::Sanitizer::Config::RELAXED, elements: ["kbd"])
+
+      Selma::Sanitizer.new(config)
+    end
+
+    def test_it_raises_on_unknown_keys
+      error = assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ element: ["b"] }) }
+
+      assert_equal("unknown config key `:element`", error.message)
+    end
+
+    def test_it_raises_on_unknown_options
+      error = assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ links: { rels: ["nofollow"] } }) }
+
+      assert_match(/`links` has unknown option `:rels`/, error.message)
+    end
+
+    def test_it_raises_on_bad_element_names
+      error = assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ elements: ["b", "<i>"] }) }
+
+      assert_equal('`elements` has an invalid element name "<i>"', error.message)
+
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ elements: ["B"] }) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ elements: ["*"] }) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ elements: ["x-*-y"] }) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ elements: ["b*"] }) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ attributes: { "a b" => ["href"] } }) }
+    end
+
+    def test_it_raises_on_bad_attribute_names
+      error = assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ attributes: { "a" => ["href=x"] } }) }
+
+      assert_equal('`attributes["a"]` has an invalid attribute name "href=x"', error.message)
+    end
+
+    def test_it_raises_on_wrong_value_types
+      error = assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ elements: "b" }) }
+
+      assert_equal('`elements` must be an Array, not "b"', error.message)
+
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ allow_comments: "no" }) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ attributes: ["href"] }) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ attribute_values: { "a" => { "rel" => 1 } } }) }
+    end
+
+    def test_it_raises_on_unknown_protocols
+      error = assert_raises(Selma::ConfigError) do
+        Selma::Sanitizer.new({ protocols: { "a" => { "href" => ["https", :relatve] } } })
+      end
+
+      assert_match(/unknown protocol :relatve/, error.message)
+    end
+
+    def test_config_errors_are_argument_errors
+      assert_raises(ArgumentError) { Selma::Sanitizer.new({ element: ["b"] }) }
+    end
+
+    def test_it_lints_dangerous_settings
+      warnings = Selma::Sanitizer::Config.lint({
+        elements: ["a", "script", "style"],
+        attributes: { "a" => ["onclick", "style"] },
+        protocols: { "a" => { "href" => ["javascript"] } },
+      })
+
+      assert_equal(
+        [
+          "`elements` allows `<script>`, which can run scripts or load other documents",
+          '`attributes["a"]` allows the `onclick` event handler',
+          "`elements` allows `<style>` without a `css:` policy, so stylesheets are kept as-is",
+          '`attributes["a"]` allows `style` without a `css:` policy, so styles are kept as-is',
+          '`protocols["a"]["href"]` allows `javascript:` URLs',
+        ],
+        warnings,
+      )
+    end
+
+    def test_it_lints_data_urls_without_a_policy
+      warnings = Selma::Sanitizer::Config.lint({
+        protocols: { "a" => { "href" => ["https", "data"] }, "img" => { "src" => ["data"] } },
+        data_uris: { "img" => { "src" => { mime_types: ["image/png"] } } },
+      })
+
+      assert_equal(['`protocols["a"]["href"]` allows every `data:` URL without a `data_uris:` policy'], warnings)
+    end
+
+    def test_it_lints_unknown_elements
+      warnings = Selma::Sanitizer::Config.lint({ elements: ["svg", "circle"] })
+
+      assert_equal(
+        ["`elements` allows `<circle>`, which is neither an HTML element nor a custom element, so every unknown element is allowed"],
+        warnings,
+      )
+    end
+
+    def test_it_accepts_symbol_element_names
+      Selma::Sanitizer.new({ elements: [:b, "my-element", "github-*"], attributes: { strong: ["title"] } })
+    end
+
+    def test_it_only_warns_when_linting
+      assert_output(nil, "") { Selma::Sanitizer.new({ elements: ["script"] }) }
+      assert_output(nil, /allows `<script>`/) { Selma::Sanitizer.new({ elements: ["script"], lint: true }) }
+    end
+  end
+end
@@ -0,0 +1,131 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerCssTest < Minitest::Test
+    describe "style attributes" do
+      def setup
+        @sanitizer = Selma::Sanitizer.new({
+          elements: ["span"],
+          attributes: { "span" => ["style"] },
+          css: {
+            properties: ["background-image", "color", "margin"],
+            protocols: ["https"],
+          },
+        })
+      end
+
+      def test_it_keeps_allowed_properties_and_normalizes_them
+        assert_equal(
+          '<span style="color: red; margin: 0 auto !important;">foo</span>',
+          Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span style="COLOR:red;position:fixed; margin :0   auto !important">foo</span>'),
+        )
+      end
+
+      def test_it_removes_the_attribute_when_nothing_is_left
+        assert_equal(
+          "<span>foo</span>",
+          Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span style="position: fixed; top: 0">foo</span>'),
+        )
+      end
+
+      def test_it_removes_expressions
+        [
+          "color: expression(alert(1))",
+          'color: e\\78 pression(alert(1))',
+          "color: expr/**/ession(alert(1))",
+        ].each do |style|
+          assert_equal(
+            "<span>foo</span>",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite(%(<span style="#{style}">foo</span>)),
+          )
+        end
+      end
+
+      def test_it_checks_url_protocols
+        assert_equal(
+          '<span style="background-image: url(https://example.com/a.png);">foo</span>',
+          Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span style="background-image: url(https://example.com/a.png)">foo</span>'),
+        )
+
+        [
+          "background-image: url(javascript:alert(1))",
+          "background-image: url('java\\73 cript:alert(1)')",
+          "background-image: url(&quot;http://example.com/a.png&quot;)",
+          "background-image: image-set('javascript:alert(1)' 1x)",
+        ].each do |style|
+          assert_equal(
+            "<span>foo</span>",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite(%(<span style="#{style}">foo</span>)),
+          )
+        end
+      end
+
+      def test_it_leaves_styles_alone_without_a_css_config
+        sanitizer = Selma::Sanitizer.new({
+          elements: ["span"],
+          attributes: { "span" => ["style"] },
+        })
+
+        assert_equal(
+          '<span style="position: fixed;">foo</span>',
+          Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<span style="position: fixed;">foo</span>'),
+        )
+      end
+    end
+
+    describe "style elements" do
+      def setup
+        @sanitizer = Selma::Sanitizer.new({
+          elements: ["p", "style"],
+          css: {
+            properties: ["color", "margin"],
+            protocols: ["https"],
+          },
+        })
+      end
+
+      def test_it_keeps_allowed_declarations_in_rules
+        assert_equal(
+          "<style>.a { color: red; }\n@media (max-width: 10px) { p { margin: 0; } }</style>",
+          Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<style>.a { color: red; position: fixed } /* x */ @media (max-width: 10px) { p { margin: 0 } .c { top: 0 } } @page { margin: 0 }</style>"),
+        )
+      end
+
+      def test_it_removes_imports_and_fonts_with_untrusted_urls
+        assert_equal(
+          "<style>@import url(https://example.com/a.css);</style>",
+          Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<style>@import url(https://example.com/a.css); @import 'http://example.com/b.css'; @font-face { font-family: x; src: url(javascript:alert(1)) }</style>"),
+        )
+      end
+
+      def test_it_scopes_selectors
+        sanitizer = Selma::Sanitizer.new({
+          elements: ["style"],
+          css: {
+            properties: ["color"],
+            scope: ".user-content",
+          },
+        })
+
+        assert_equal(
+          "<style>.user-content { color: red; }\n.user-content .a, .user-content p { color: blue; }</style>",
+          Selma::Rewriter.new(sanitizer: sanitizer).rewrite("<style>body { color: red } .a, p { color: blue }</style>"),
+        )
+      end
+
+      def test_it_removes_style_elements_that_are_not_allowed
+        sanitizer = Selma::Sanitizer.new({
+          elements: ["p"],
+          css: { properties: ["color"] },
+        })
+
+        assert_equal(
+          "<p>foo</p>",
+          Selma::Rewriter.new(sanitizer: sanitizer).rewrite("<style>p { color: red }</style><p>foo</p>"),
+        )
+      end
+    end
+  end
+end
@@ -0,0 +1,55 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerCustomElementsTest < Minitest::Test
+    def setup
+      @config = {
+        elements: ["b", "x-emoji", "github-*"],
+        attributes: { "x-emoji" => ["name"], "github-*" => ["data-id"] },
+      }
+    end
+
+    def rewrite(html, sanitizer = Selma::Sanitizer.new(@config))
+      Selma::Rewriter.new(sanitizer: sanitizer).rewrite(html)
+    end
+
+    def test_it_allows_custom_elements_by_name
+      assert_equal(
+        '<x-emoji name="tada"></x-emoji>b',
+        rewrite('<x-emoji name="tada" class="big"></x-emoji><x-other>b</x-other>'),
+      )
+    end
+
+    def test_it_allows_custom_elements_by_prefix
+      assert_equal(
+        '<github-avatar data-id="1">a</github-avatar>',
+        rewrite('<github-avatar data-id="1" onclick="x()">a</github-avatar>'),
+      )
+    end
+
+    def test_it_prefers_the_longest_prefix
+      sanitizer = Selma::Sanitizer.new(@config)
+      sanitizer.allow_element(["github-secret-*"])
+      sanitizer.disallow_element(["github-secret-*"])
+
+      assert_equal("<github-avatar>a</github-avatar>b", rewrite("<github-avatar>a</github-avatar><github-secret-token>b</github-secret-token>", sanitizer))
+    end
+
+    def test_it_rejects_invalid_custom_element_names
+      sanitizer = Selma::Sanitizer.new(@config)
+
+      ["*", "x*", "-x", "1-x", "x_y*", "font-face", "a!-b"].each do |name|
+        assert_raises(ArgumentError) { sanitizer.allow_element([name]) }
+        assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ elements: [name] }) }
+      end
+
+      assert_equal(["b", "x-emoji", "github-*"], sanitizer.allowed_elements)
+    end
+
+    def test_it_reports_custom_elements
+      assert_equal(["b", "x-emoji", "github-*"], Selma::Sanitizer.new(@config).allowed_elements)
+    end
+  end
+end
@@ -0,0 +1,97 @@
+# frozen_string_literal: true
+
+require "test_helper"
+require "json"
+
+module Selma
+  class SanitizerDataUrisTest < Minitest::Test
+    PNG = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg=="
+
+    def config
+      {
+        elements: ["a", "img"],
+        attributes: { "a" => ["href"], "img" => ["src", "srcset"] },
+        protocols: { "a" => { "href" => ["https", "data"] }, "img" => { "src" => ["https"] } },
+        data_uris: { "img" => { "src" => { mime_types: ["image/png", "image/gif", "image/webp"], max_size: 1024 } } },
+      }
+    end
+
+    def test_it_keeps_allowed_images
+      sanitizer = Selma::Sanitizer.new(config)
+
+      assert_equal(%(<img src="data:image/png;base64,#{PNG}">), sanitizer.sanitize(%(<img src="data:image/png;base64,#{PNG}">)))
+      assert_equal(%(<img src="data:IMAGE/PNG;Base64,#{PNG}">), sanitizer.sanitize(%(<img src="data:IMAGE/PNG;Base64,#{PNG}">)))
+    end
+
+    def test_it_drops_other_mime_types
+      sanitizer = Selma::Sanitizer.new(config)
+
+      assert_equal("<img>", sanitizer.sanitize('<img src="data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==">'))
+      assert_equal("<img>", sanitizer.sanitize('<img src="data:image/svg+xml;base64,PHN2Zz48L3N2Zz4=">'))
+      assert_equal("<img>", sanitizer.sanitize("<img src=\"data:;base64,#{PNG}\">"))
+    end
+
+    def test_it_requires_well_formed_base64
+      sanitizer = Selma::Sanitizer.new(config)
+
+      assert_equal("<img>", sanitizer.sanitize('<img src="data:image/png,%89PNG">'))
+      assert_equal("<img>", sanitizer.sanitize('<img src="data:image/png;base64,iVBORw0KGgo%3D">'))
+      assert_equal("<img>", sanitizer.sanitize('<img src="data:image/png;base64,iVBORw0KGg">'))
+      assert_equal("<img>", sanitizer.sanitize('<img src="data:image/png;base64,iVBO=Rw0K">'))
+      assert_equal("<img>", sanitizer.sanitize('<img src="data:image/png ;base64,iVBORw0K">'))
+    end
+
+    def test_it_limits_the_decoded_size
+      sanitizer = Selma::Sanitizer.new(config)
+      payload = "A" * 1368
+
+      assert_equal("<img>", sanitizer.sanitize(%(<img src="data:image/gif;base64,#{payload}">)))
+      assert_equal(
+        %(<img src="data:image/gif;base64,#{payload[0, 1364]}">),
+        sanitizer.sanitize(%(<img src="data:image/gif;base64,#{payload[0, 1364]}">)),
+      )
+    end
+
+    def test_policies_are_per_attribute
+      sanitizer = Selma::Sanitizer.new(config)
+
+      # `a[href]` allows `data:` outright, while `img[src]` only allows images
+      assert_equal(
+        '<a href="data:text/html;base64,PGI+PC9iPg==">x</a>',
+        sanitizer.sanitize('<a href="data:text/html;base64,PGI+PC9iPg==">x</a>'),
+      )
+
+      sanitizer = Selma::Sanitizer.new(config.merge(data_uris: { "a" => { "href" => { mime_types: ["image/png"] } } }))
+
+      assert_equal("<a>x</a>", sanitizer.sanitize('<a href="data:text/html;base64,PGI+PC9iPg==">x</a>'))
+    end
+
+    def test_it_checks_srcset_candidates
+      sanitizer = Selma::Sanitizer.new(config)
+
+      assert_equal(
+        %(<img srcset="data:image/png;base64,#{PNG} 1x, https://example.com/a.png 2x">),
+        sanitizer.sanitize(%(<img srcset="data:image/png;base64,#{PNG} 1x, data:text/html;base64,PGI+ 2x, https://example.com/a.png 2x">)),
+      )
+    end
+
+    def test_it_validates_policies
+      error = assert_raises(Selma::ConfigError) do
+        Selma::Sanitizer.new(config.merge(data_uris: { "img" => { "src" => { mime_types: ["png"] } } }))
+      end
+      assert_match(/invalid MIME type "png"/, error.message)
+
+      error = assert_raises(Selma::ConfigError) do
+        Selma::Sanitizer.new(config.merge(data_uris: { "img" => { "src" => { max_size: -1 } } }))
+      end
+      assert_match(/non-negative Integer/, error.message)
+    end
+
+    def test_it_round_trips_through_policies
+      sanitizer = Selma::Sanitizer.from_json(Selma::Sanitizer.new(config).to_json)
+
+      assert_equal("<img>", sanitizer.sanitize('<img src="data:text/html;base64,PGI+PC9iPg==">'))
+      assert_equal(%(<img src="data:image/png;base64,#{PNG}">), sanitizer.sanitize(%(<img src="data:image/png;base64,#{PNG}">)))
+    end
+  end
+end
@@ -4,6 +4,27 @@ require "test_helper"
 
 module Selma
   class SanitizerDoctypeTest < Minitest::Test
+    class DoctypeHandler
+      SELECTOR = Selma::Selector.new(match_element: "html")
+
+      attr_reader :doctypes
+
+      def initialize
+        @doctypes = []
+      end
+
+      def selector
+        SELECTOR
+      end
+
+      def handle_element(element); end
+
+      def handle_doctype(doctype)
+        @doctypes << [doctype.name, doctype.public_id, doctype.system_id]
+        doctype.remove if doctype.public_id
+      end
+    end
+
     describe "sanitization" do
       context "when :allow_doctype is false" do
         def setup
@@ -19,28 +40,6 @@ module Selma
         end
       end
 
-      def test_blocks_invalid_doctypes_in_documents
-        skip("non-essential feature")
-        @sanitizer = Selma::Sanitizer.new({ allow_doctype: true, elements: ["html"] })
-
-        assert_equal(
-          "<!DOCTYPE html><html>foo</html>",
-          Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!DOCTYPE blah blah blah><html>foo</html>"),
-        )
-        assert_equal(
-          "<!DOCTYPE html><html>foo</html>",
-          Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!DOCTYPE blah><html>foo</html>"),
-        )
-        assert_equal(
-          "<!DOCTYPE html><html>foo</html>",
-          Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<!DOCTYPE html BLAH "-//W3C//DTD HTML 4.01//EN"><html>foo</html>'),
-        )
-        assert_equal(
-          "<!DOCTYPE html><html>foo</html>",
-          Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!whatever><html>foo</html>"),
-        )
-      end
-
       context "when :allow_doctype is true" do
         def setup
           @sanitizer = Selma::Sanitizer.new({ allow_doctype: true, elements: ["html"] })
@@ -62,29 +61,63 @@ module Selma
             ),
           )
         end
+      end
 
-        def test_blocks_invalid_doctypes_in_documents
-          skip("non-essential feature")
+      context "when :allow_doctype is :normalize" do
+        def setup
+          @sanitizer = Selma::Sanitizer.new({ allow_doctype: :normalize, elements: ["html"] })
+        end
 
+        def test_it_normalizes_doctypes
           assert_equal(
             "<!DOCTYPE html><html>foo</html>",
-            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!DOCTYPE blah blah blah><html>foo</html>"
-                .rewrite(sanitizer: @sanitizer)),
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!DOCTYPE blah blah blah><html>foo</html>"),
           )
           assert_equal(
             "<!DOCTYPE html><html>foo</html>",
-            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!DOCTYPE blah><html>foo</html>"),
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<!DOCTYPE html BLAH "-//W3C//DTD HTML 4.01//EN"><html>foo</html>'),
           )
+          assert_equal("<!DOCTYPE html>", Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!doctype html>"))
+        end
+      end
+
+      context "when :allow_doctype is :reject_legacy" do
+        def setup
+          @sanitizer = Selma::Sanitizer.new({ allow_doctype: :reject_legacy, elements: ["html"] })
+        end
+
+        def test_it_removes_legacy_doctypes
           assert_equal(
-            "<!DOCTYPE html><html>foo</html>",
-            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<!DOCTYPE html BLAH "-//W3C//DTD HTML 4.01//EN"><html>foo</html>'),
+            "<html>foo</html>",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01//EN"><html>foo</html>'),
+          )
+          assert_equal(
+            "<html>foo</html>",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!DOCTYPE blah><html>foo</html>"),
           )
           assert_equal(
             "<!DOCTYPE html><html>foo</html>",
-            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!whatever><html>foo</html>"),
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite("<!DOCTYPE html><html>foo</html>"),
           )
         end
       end
+
+      def test_it_rejects_unknown_doctype_policies
+        assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ allow_doctype: :strict }) }
+      end
+
+      def test_it_passes_doctypes_to_handlers
+        handler = DoctypeHandler.new
+        html = Selma::Rewriter.new(sanitizer: nil, handlers: [handler]).rewrite(
+          '<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"><html>foo</html>',
+        )
+
+        assert_equal("<html>foo</html>", html)
+        assert_equal(
+          [["html", "-//W3C//DTD XHTML 1.0 Strict//EN", "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"]],
+          handler.doctypes,
+        )
+      end
     end
   end
 end
@@ -252,6 +252,57 @@ module Selma
           assert_equal("<a>Footnote 1</a>", Selma::Rewriter.new(sanitizer: sanitizer).rewrite(input))
         end
 
+        def test_should_only_allow_protocol_relative_urls_when_allowlisted
+          input = '<a href="//example.com/foo">Link</a>'
+
+          sanitizer = Selma::Sanitizer.new({
+            elements: ["a"],
+            attributes: { "a" => ["href"] },
+            protocols: { "a" => { "href" => ["https", :relative] } },
+          })
+
+          assert_equal("<a>Link</a>", Selma::Rewriter.new(sanitizer: sanitizer).rewrite(input))
+
+          sanitizer = Selma::Sanitizer.new({
+            elements: ["a"],
+            attributes: { "a" => ["href"] },
+            protocols: { "a" => { "href" => ["https", :protocol_relative] } },
+          })
+
+          assert_equal(input, Selma::Rewriter.new(sanitizer: sanitizer).rewrite(input))
+        end
+
+        def test_should_not_allow_url_schemes_when_no_protocols_are_configured
+          sanitizer = Selma::Sanitizer.new({
+            elements: ["a"],
+            attributes: { "a" => ["href", "title"] },
+          })
+
+          assert_equal("<a>Link</a>", Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<a href="javascript:alert(1)">Link</a>'))
+
+          input = '<a href="/foo" title="Note: a link">Link</a>'
+
+          assert_equal(input, Selma::Rewriter.new(sanitizer: sanitizer).rewrite(input))
+        end
+
+        def test_should_check_every_srcset_candidate
+          sanitizer = Selma::Sanitizer.new({
+            elements: ["img"],
+            attributes: { "img" => ["src", "srcset"] },
+            protocols: { "img" => { "src" => ["https", :relative] } },
+          })
+
+          assert_equal(
+            '<img srcset="https://example.com/a.png 1x, b.png 2x">',
+            Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<img srcset="https://example.com/a.png 1x, javascript:alert(1) 2x,b.png  2x">'),
+          )
+
+          assert_equal(
+            "<img>",
+            Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<img srcset="http://example.com/a.png 1x">'),
+          )
+        end
+
         def test_should_remove_the_contents_of_filtered_nodes_when_remove_contents_is_true
           sanitizer = Selma::Sanitizer.new({ remove_contents: true })
 
@@ -0,0 +1,58 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerEmptyElementsTest < Minitest::Test
+    def setup
+      @config = {
+        elements: ["a", "b", "p", "span"],
+        attributes: { "a" => ["href"] },
+        protocols: { "a" => { "href" => ["https"] } },
+      }
+    end
+
+    def test_it_removes_elements_left_empty
+      sanitizer = Selma::Sanitizer.new(@config.merge(remove_empty_elements: ["a", "p"]))
+
+      assert_equal(
+        "foo  bar",
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('foo <p><a href="https://example.com"><script>alert(1)</script></a></p> bar'),
+      )
+    end
+
+    def test_it_keeps_elements_with_content
+      sanitizer = Selma::Sanitizer.new(@config.merge(remove_empty_elements: ["a", "p"]))
+
+      assert_equal(
+        '<p><a href="https://example.com">foo</a><b></b></p>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<p><a href="https://example.com">foo</a><b></b></p>'),
+      )
+    end
+
+    def test_it_keeps_whitespace_only_elements_by_default
+      sanitizer = Selma::Sanitizer.new(@config.merge(remove_empty_elements: ["span"]))
+
+      assert_equal(
+        "foo <span> </span> bar",
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite("foo <span> </span> bar"),
+      )
+    end
+
+    def test_it_keeps_comments_in_order
+      sanitizer = Selma::Sanitizer.new(@config.merge(allow_comments: true, remove_empty_elements: ["p"]))
+
+      assert_equal("<p><!--c-->x</p>", Selma::Rewriter.new(sanitizer: sanitizer).rewrite("<p><!--c-->x</p>"))
+      assert_equal("foo  bar", Selma::Rewriter.new(sanitizer: sanitizer).rewrite("foo <p><!--c--></p> bar"))
+    end
+
+    def test_it_can_remove_whitespace_only_elements
+      sanitizer = Selma::Sanitizer.new(@config.merge(remove_empty_elements: { elements: ["span"], whitespace_only: true }))
+
+      assert_equal(
+        "foo   bar <span> baz</span>",
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite("foo <span> </span> bar <span> baz</span>"),
+      )
+    end
+  end
+end
@@ -0,0 +1,51 @@
+# frozen_string_literal: true
+
+require "test_helper"
+require "json"
+
+module Selma
+  class SanitizerEscapeTest < Minitest::Test
+    def test_it_escapes_listed_elements
+      sanitizer = Selma::Sanitizer.new({ elements: ["b"], escape_elements: ["div"] })
+
+      assert_equal(
+        '&lt;div class="x"&gt;hi <b>y</b>&lt;/div&gt;z',
+        sanitizer.sanitize('<div class="x">hi <b>y</b></div><blink>z</blink>'),
+      )
+    end
+
+    def test_it_escapes_attribute_values_as_written
+      sanitizer = Selma::Sanitizer.new({ escape_elements: ["div"] })
+
+      assert_equal(
+        '&lt;div title="a&amp;amp;b &amp;quot;c&amp;quot; &lt;d&gt;"&gt;x&lt;/div&gt;',
+        sanitizer.sanitize('<div title="a&amp;b &quot;c&quot; <d>">x</div>'),
+      )
+    end
+
+    def test_it_escapes_raw_text
+      sanitizer = Selma::Sanitizer.new(Selma::Sanitizer::Config::DEFAULT.merge(escape_elements: ["script", "title"]))
+
+      assert_equal(
+        "&lt;script&gt;alert('&lt;b&gt;')&lt;/script&gt;",
+        sanitizer.sanitize("<script>alert('<b>')</script>"),
+      )
+      assert_equal("&lt;title&gt;a &amp; b&lt;/title&gt;", sanitizer.sanitize("<title>a &amp; b</title>"))
+    end
+
+    def test_it_escapes_every_disallowed_element_by_default
+      sanitizer = Selma::Sanitizer.new({ elements: ["b"], escape_elements: true })
+
+      assert_equal(
+        '<b>x</b>&lt;img src="y" onerror="alert(1)"&gt;&lt;br /&gt;',
+        sanitizer.sanitize("<b>x</b><img src=y onerror=alert(1)><br/>"),
+      )
+    end
+
+    def test_it_exports_escaped_elements
+      sanitizer = Selma::Sanitizer.new({ escape_elements: ["script"] })
+
+      assert_equal(["script"], JSON.parse(sanitizer.to_json)["escape_elements"])
+    end
+  end
+end
@@ -0,0 +1,93 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerForeignContentTest < Minitest::Test
+    def setup
+      @config = {
+        elements: ["b", "p"],
+        svg: {
+          elements: ["svg", "g", "path", "use", "foreignObject", "title"],
+          attributes: { all: ["fill", "id"], "svg" => ["viewBox"], "path" => ["d"], "use" => ["href", "xlink:href"] },
+        },
+        math: { elements: ["math", "mi", "mo", "mtext", "mglyph"] },
+      }
+    end
+
+    def rewrite(html, config = @config)
+      Selma::Rewriter.new(sanitizer: Selma::Sanitizer.new(config)).rewrite(html)
+    end
+
+    def test_it_keeps_allowed_svg_regardless_of_case
+      assert_equal(
+        '<svg viewBox="0 0 1 1"><path d="M0 0"></path></svg>',
+        rewrite('<svg viewBox="0 0 1 1" onload="alert(1)"><path d="M0 0" onclick="alert(1)"></path></svg>'),
+      )
+      assert_equal(
+        "<svg><foreignObject><p>a</p>b</foreignObject><title>t</title></svg>",
+        rewrite("<svg><foreignObject><p>a</p><i>b</i></foreignObject><title>t</title></svg>"),
+      )
+    end
+
+    def test_it_unwraps_other_svg_elements
+      assert_equal('<svg><path d="x" /></svg>', rewrite('<svg><circle/><rect><path d="x" /></rect></svg>'))
+    end
+
+    def test_it_blocks_external_references
+      assert_equal(
+        '<svg><use href="#a"></use><use></use><use></use><g></g><g fill="url(#g)"></g></svg>',
+        rewrite(
+          '<svg><use href="#a"></use><use href="https://example.com/x.svg#a"></use><use xlink:href="//example.com/x#a"></use>' \
+            %(<g fill="url( 'https://example.com/x')"></g><g fill="url(#g)"></g></svg>),
+        ),
+      )
+    end
+
+    def test_it_blocks_animations_of_links
+      config = { svg: { elements: ["svg", "a", "set", "animate"], attributes: { all: ["attributeName", "to", "values"] } } }
+
+      assert_equal(
+        '<svg><a><set to="javascript:alert(1)" /><animate values="javascript:alert(1)"></animate></a></svg>',
+        rewrite(
+          '<svg><a><set attributeName="href" to="javascript:alert(1)"/>' \
+            '<animate attributeName=" xlink:href " values="javascript:alert(1)"></animate></a></svg>',
+          config,
+        ),
+      )
+      assert_equal(
+        '<svg><animate attributeName="fill" values="red;blue"></animate></svg>',
+        rewrite('<svg><animate attributeName="fill" values="red;blue"></animate></svg>', config),
+      )
+    end
+
+    def test_it_removes_raw_text_elements_from_foreign_content
+      assert_equal("<svg></svg>", rewrite("<svg><style><img src=x onerror=alert(1)></style></svg>"))
+    end
+
+    def test_it_neutralizes_mathml_namespace_confusion
+      assert_equal(
+        "<math><mtext></mtext></math>",
+        rewrite(%(<math><mtext><table><mglyph><style><!--</style><img title="--&gt;&lt;img src=1 onerror=alert(1)&gt;"></mglyph></table></mtext></math>)),
+      )
+      assert_equal("<math><mtext><mglyph></mglyph></mtext></math>", rewrite("<math><mtext><mglyph></mglyph></mtext></math>"))
+      assert_equal("<math></math>", rewrite("<math><mglyph></mglyph></math>"))
+    end
+
+    def test_it_removes_disallowed_integration_points_with_their_contents
+      assert_equal("<math></math>", rewrite("<math><ms><b>x</b></ms></math>"))
+    end
+
+    def test_it_rejects_bad_foreign_configs
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ svg: { elements: ["style"] } }) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ svg: { element: ["svg"] } }) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ math: { attributes: { "mi" => ["a*"] } } }) }
+    end
+
+    def test_it_lints_foreign_event_handlers
+      warnings = Selma::Sanitizer::Config.lint({ svg: { attributes: { "svg" => ["onload"] } } })
+
+      assert_equal(['`svg[:attributes]["svg"]` allows the `onload` event handler'], warnings)
+    end
+  end
+end
@@ -0,0 +1,80 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerHeadTest < Minitest::Test
+    def setup
+      @config = {
+        elements: ["html", "head", "meta", "base", "link", "body"],
+        attributes: {
+          "meta" => ["name", "property", "content", "http-equiv"],
+          "base" => ["href", "target"],
+          "link" => ["rel", "href"],
+        },
+        protocols: {
+          "base" => { "href" => ["https"] },
+          "link" => { "href" => ["https", :relative] },
+        },
+        head: {},
+      }
+    end
+
+    def rewrite(html, config = @config)
+      Selma::Rewriter.new(sanitizer: Selma::Sanitizer.new(config)).rewrite(html)
+    end
+
+    def test_it_removes_refreshing_and_cookie_setting_meta_tags
+      assert_equal(
+        '<meta name="description" content="x"><meta property="og:title" content="t">',
+        rewrite(
+          '<meta name="description" content="x"><meta http-equiv="Refresh" content="0;url=https://example.com">' \
+            '<meta property="og:title" content="t"><meta http-equiv="set-cookie" content="a=b">',
+        ),
+      )
+    end
+
+    def test_it_restricts_base_hrefs
+      assert_equal('<base target="_top">', rewrite('<base href="https://example.com/"><base target="_top">'))
+
+      config = @config.merge(head: { base_hosts: ["example.com"] })
+
+      assert_equal(
+        '<base href="https://www.example.com/">',
+        rewrite('<base href="https://www.example.com/"><base href="https://example.com.evil/">', config),
+      )
+    end
+
+    def test_it_restricts_link_rels
+      assert_equal(
+        '<link rel="canonical" href="/a">',
+        rewrite('<link rel="canonical" href="/a"><link rel="preload" href="/x"><link rel="stylesheet" href="/a.css">'),
+      )
+    end
+
+    def test_it_only_allows_stylesheets_from_allowed_hosts
+      config = @config.merge(head: { link_rels: ["stylesheet"], stylesheet_hosts: ["cdn.example.com"] })
+
+      assert_equal(
+        '<link rel="stylesheet" href="https://cdn.example.com/a.css">',
+        rewrite(
+          '<link rel="stylesheet" href="https://cdn.example.com/a.css"><link rel="stylesheet" href="https://evil.example/a.css">' \
+            '<link rel="icon" href="/i.png">',
+          config,
+        ),
+      )
+    end
+
+    def test_it_leaves_head_elements_alone_without_a_head_config
+      config = @config.except(:head)
+      html = '<meta http-equiv="refresh" content="0"><base href="https://example.com/"><link rel="stylesheet" href="/a.css">'
+
+      assert_equal(html, rewrite(html, config))
+    end
+
+    def test_it_rejects_bad_head_configs
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ head: { base_host: ["example.com"] } }) }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ head: { stylesheet_hosts: [1] } }) }
+    end
+  end
+end
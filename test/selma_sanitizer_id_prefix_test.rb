@@ -0,0 +1,50 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerIdPrefixTest < Minitest::Test
+    def setup
+      @sanitizer = Selma::Sanitizer.new({
+        elements: ["a", "form", "span"],
+        attributes: { "a" => ["href"], "form" => ["name"], "span" => ["id"] },
+        protocols: { "a" => { "href" => ["https", :relative] } },
+        id_prefix: "user-content-",
+      })
+    end
+
+    def test_it_prefixes_ids_and_names
+      assert_equal(
+        '<span id="user-content-__proto__">foo</span><form name="user-content-forms"></form>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<span id="__proto__">foo</span><form name="forms"></form>'),
+      )
+    end
+
+    def test_it_rewrites_fragment_links
+      assert_equal(
+        '<a href="#user-content-fn-1">1</a><span id="user-content-fn-1">foo</span>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<a href="#fn-1">1</a><span id="fn-1">foo</span>'),
+      )
+    end
+
+    def test_it_prefixes_labels_and_aria_relationships
+      sanitizer = Selma::Sanitizer.new({
+        elements: ["input", "label", "span"],
+        attributes: { "input" => ["id", "aria-describedby"], "label" => ["for"], "span" => ["id"] },
+        id_prefix: "user-content-",
+      })
+
+      assert_equal(
+        '<label for="user-content-q">Q</label><input id="user-content-q" aria-describedby="user-content-hint user-content-err">',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<label for="q">Q</label><input id="q" aria-describedby="hint  user-content-err">'),
+      )
+    end
+
+    def test_it_leaves_other_links_and_prefixed_values_alone
+      assert_equal(
+        '<a href="https://example.com/#foo">1</a><a href="#user-content-bar">2</a><a href="#">3</a>',
+        Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('<a href="https://example.com/#foo">1</a><a href="#user-content-bar">2</a><a href="#">3</a>'),
+      )
+    end
+  end
+end
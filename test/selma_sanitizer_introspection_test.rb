@@ -0,0 +1,67 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerIntrospectionTest < Minitest::Test
+    def sanitizer
+      Selma::Sanitizer.new({
+        elements: ["a", "b", "img"],
+        attributes: { all: ["title"], "a" => ["href", "rel"] },
+        protocols: { "a" => { "href" => ["http", "https", :relative] } },
+      })
+    end
+
+    def test_it_reports_the_compiled_policy
+      sanitizer = self.sanitizer
+
+      assert_equal(["a", "b", "img"], sanitizer.allowed_elements)
+      assert_equal(["href", "rel", "title"], sanitizer.allowed_attributes(for: "a"))
+      assert_equal(["title"], sanitizer.allowed_attributes)
+      assert_equal(["http", "https", :relative], sanitizer.allowed_protocols(for: "a", attr: "href"))
+      assert_empty(sanitizer.allowed_protocols(for: "img", attr: "src"))
+    end
+
+    def test_it_disallows_per_element_attributes
+      sanitizer = self.sanitizer
+      sanitizer.disallow_attribute("a", ["rel"])
+
+      assert_equal(["href", "title"], sanitizer.allowed_attributes(for: "a"))
+      assert_equal(
+        '<a href="https://example.com" title="x">a</a>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<a href="https://example.com" rel="me" title="x">a</a>'),
+      )
+    end
+
+    def test_it_disallows_elements_and_protocols
+      sanitizer = self.sanitizer
+      sanitizer.disallow_element(["img"])
+      sanitizer.disallow_protocol("a", "href", ["http"])
+
+      assert_equal(["a", "b"], sanitizer.allowed_elements)
+      assert_equal(["https", :relative], sanitizer.allowed_protocols(for: "a", attr: "href"))
+      assert_equal(
+        "<a>a</a><a href=\"/b\">b</a>",
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<a href="http://example.com">a</a><a href="/b">b</a><img src="/c.png">'),
+      )
+    end
+
+    def test_it_disallows_classes
+      sanitizer = Selma::Sanitizer.new({ elements: ["span"], attributes: { "span" => ["class"] } })
+      sanitizer.allow_class("span", "a", /b\d/)
+      sanitizer.disallow_class("span", "a")
+
+      assert_equal(
+        '<span class="b1">x</span>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<span class="a b1 c">x</span>'),
+      )
+    end
+
+    def test_it_turns_off_remove_contents_for_all_elements
+      sanitizer = Selma::Sanitizer.new({ elements: ["b"], remove_contents: ["i"] })
+      sanitizer.remove_contents(false)
+
+      assert_equal("<b>a</b>b", Selma::Rewriter.new(sanitizer: sanitizer).rewrite("<b>a</b><i>b</i>"))
+    end
+  end
+end
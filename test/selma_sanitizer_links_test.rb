@@ -0,0 +1,68 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerLinksTest < Minitest::Test
+    def setup
+      @config = {
+        elements: ["a"],
+        attributes: { "a" => ["href", "rel", "target"] },
+        protocols: { "a" => { "href" => ["https", :relative] } },
+      }
+    end
+
+    def test_it_merges_rel_tokens
+      sanitizer = Selma::Sanitizer.new(@config.merge(links: { rel: ["nofollow", "ugc"] }))
+
+      assert_equal(
+        '<a href="https://example.com" rel="me nofollow ugc">foo</a>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<a href="https://example.com" rel="me">foo</a>'),
+      )
+    end
+
+    def test_it_forces_rel_tokens
+      sanitizer = Selma::Sanitizer.new(@config.merge(links: { rel: ["nofollow"], rel_mode: :force }))
+
+      assert_equal(
+        '<a href="https://example.com" rel="nofollow">foo</a>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<a href="https://example.com" rel="me">foo</a>'),
+      )
+    end
+
+    def test_it_raises_on_unknown_rel_modes
+      assert_raises(ArgumentError) do
+        sanitizer = Selma::Sanitizer.new(@config.merge(links: { rel: ["nofollow"], rel_mode: :sometimes }))
+        Selma::Rewriter.new(sanitizer: sanitizer)
+      end
+    end
+
+    def test_it_restricts_targets_and_adds_noopener
+      sanitizer = Selma::Sanitizer.new(@config.merge(links: { targets: ["_blank"] }))
+
+      assert_equal(
+        '<a href="https://example.com" target="_blank" rel="noopener">foo</a>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<a href="https://example.com" target="_blank">foo</a>'),
+      )
+
+      assert_equal(
+        '<a href="https://example.com">foo</a>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<a href="https://example.com" target="_top">foo</a>'),
+      )
+    end
+
+    def test_it_leaves_internal_links_alone
+      sanitizer = Selma::Sanitizer.new(@config.merge(links: { rel: ["nofollow"], internal_hosts: ["example.com"] }))
+
+      assert_equal(
+        '<a href="https://docs.example.com/">foo</a> <a href="/bar">bar</a>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<a href="https://docs.example.com/">foo</a> <a href="/bar">bar</a>'),
+      )
+
+      assert_equal(
+        '<a href="https://example.com.evil.com/" rel="nofollow">foo</a>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<a href="https://example.com.evil.com/">foo</a>'),
+      )
+    end
+  end
+end
@@ -0,0 +1,73 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerModesTest < Minitest::Test
+    def sanitizer
+      Selma::Sanitizer.new({
+        elements: ["b", "html", "head", "body", "title"],
+        attributes: { "html" => ["lang"] },
+        allow_doctype: true,
+      })
+    end
+
+    def test_fragments_drop_document_elements
+      html = "<!DOCTYPE html><html lang=\"en\"><head><title>t</title></head><body><b>x</b></body></html>"
+
+      assert_equal("<b>x</b>", sanitizer.sanitize(html))
+    end
+
+    def test_documents_keep_their_skeleton
+      html = "<!DOCTYPE html><html lang=\"en\" onload=\"x\"><body><b>x</b></body></html>"
+
+      assert_equal(
+        "<!DOCTYPE html><html lang=\"en\"><head></head><body><b>x</b></body></html>",
+        sanitizer.sanitize_document(html),
+      )
+    end
+
+    def test_documents_get_a_skeleton
+      assert_equal(
+        "<html><head><title>t</title></head><body><b>x</b></body></html>",
+        sanitizer.sanitize_document("<title>t</title><b>x</b>"),
+      )
+      assert_equal("<html><head></head><body></body></html>", sanitizer.sanitize_document(""))
+    end
+
+    def test_documents_get_a_skeleton_even_when_it_is_not_allowed
+      sanitizer = Selma::Sanitizer.new({ elements: ["b"] })
+
+      assert_equal(
+        "<html><head></head><body><b>x</b></body></html>",
+        sanitizer.sanitize_document("<html><body><b>x</b></body></html>"),
+      )
+    end
+
+    def test_documents_get_a_skeleton_around_what_is_kept
+      sanitizer = Selma::Sanitizer.new({ elements: ["b", "title"], escape_elements: ["i"] })
+
+      assert_equal(
+        "<html><head><title>t</title></head><body>&lt;i&gt;<b>x</b>&lt;/i&gt;</body></html>",
+        sanitizer.sanitize_document("<title>t</title><script>y</script><i><b>x</b></i>"),
+      )
+    end
+
+    def test_rewriter_modes
+      html = "<!DOCTYPE html><html><body><b>x</b></body></html>"
+
+      assert_equal("<b>x</b>", Selma::Rewriter.new(sanitizer: sanitizer, mode: :fragment).rewrite(html))
+      assert_equal(
+        "<!DOCTYPE html><html><head></head><body><b>x</b></body></html>",
+        Selma::Rewriter.new(sanitizer: sanitizer, mode: :document).rewrite(html),
+      )
+      assert_equal(html, Selma::Rewriter.new(sanitizer: sanitizer).rewrite(html))
+    end
+
+    def test_rewriter_rejects_unknown_modes
+      assert_raises(ArgumentError) do
+        Selma::Rewriter.new(sanitizer: sanitizer, mode: :partial)
+      end
+    end
+  end
+end
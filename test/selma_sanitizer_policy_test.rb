@@ -0,0 +1,100 @@
+# frozen_string_literal: true
+
+require "test_helper"
+require "json"
+
+module Selma
+  class SanitizerPolicyTest < Minitest::Test
+    POLICY_JSON = <<~JSON
+      {
+        "elements": ["a", "p", "span"],
+        "attributes": { "a": ["href"], "span": ["class"] },
+        "classes": { "span": [{ "pattern": "hl-[a-z]+", "case_insensitive": true }] },
+        "protocols": { "a": { "href": ["https", ":relative"] } },
+        "remove_contents": ["script"]
+      }
+    JSON
+
+    def test_it_loads_json_policies
+      sanitizer = Selma::Sanitizer.from_json(POLICY_JSON)
+
+      assert_equal(
+        '<p><a href="https://example.com">a</a><a>b</a><span class="HL-X">c</span></p>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<p><a href="https://example.com">a</a><a href="javascript:alert(1)">b</a><span class="HL-X other">c</span><script>d</script></p>'),
+      )
+    end
+
+    def test_it_loads_yaml_policies
+      sanitizer = Selma::Sanitizer.from_yaml(<<~YAML)
+        elements: [b, em]
+        remove_contents: true
+      YAML
+
+      assert_equal("<b>a</b>", Selma::Rewriter.new(sanitizer: sanitizer).rewrite("<b>a</b><i>b</i>"))
+    end
+
+    def test_it_raises_on_invalid_policies
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.from_json("{") }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.from_json('{ "element": ["b"] }') }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.from_yaml("elements: ['<b>']") }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.from_yaml("elements: [b") }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.from_yaml("- b") }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.from_yaml("protocols: { a: { href: [:relative] } }") }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.from_yaml("elements: &e [b]\nwhitespace_elements: *e") }
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.from_json('{ "protocols": { "a": { "href": [":relatve"] } } }') }
+    end
+
+    def test_it_exports_the_effective_policy
+      sanitizer = Selma::Sanitizer.new({
+        elements: ["a"],
+        attributes: { "a" => ["href"] },
+        protocols: { "a" => { "href" => ["https", :relative] } },
+      })
+      policy = JSON.parse(sanitizer.to_json)
+
+      assert_equal(["a"], policy["elements"])
+      assert_equal({ "a" => { "href" => ["https", ":relative"] } }, policy["protocols"])
+    end
+
+    def test_yaml_policies_round_trip_symbols_and_patterns
+      sanitizer = Selma::Sanitizer.new({
+        elements: ["a", "span"],
+        attributes: { "a" => ["href"], "span" => ["class", "title"] },
+        classes: { "span" => [/hl-[a-z]+/i] },
+        attribute_values: { "span" => { "title" => { pattern: /[\w ]+/, max_length: 10 } } },
+        protocols: { "a" => { "href" => ["https", :relative, :protocol_relative] } },
+        required_attributes: { "a" => { attributes: ["href"], action: :remove } },
+      })
+      html = '<a href="/a">a</a><a href="//example.com">b</a><a href="http://example.com">c</a><a>d</a>' \
+        '<span class="HL-x other" title="ok">e</span><span title="no!">f</span>'
+
+      yaml = sanitizer.to_yaml
+      from_yaml = Selma::Sanitizer.from_yaml(yaml)
+
+      assert_includes(yaml, %(- ":relative"))
+      assert_equal(sanitizer.to_json, from_yaml.to_json)
+      assert_equal(
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite(html),
+        Selma::Rewriter.new(sanitizer: from_yaml).rewrite(html),
+      )
+    end
+
+    def test_exported_policies_round_trip
+      sanitizer = Selma::Sanitizer.new(Selma::Sanitizer::Config::RELAXED)
+      html = '<a href="https://example.com" onclick="x">a</a><img src="//example.com/a.png"><script>b</script>'
+
+      from_json = Selma::Sanitizer.from_json(sanitizer.to_json)
+      from_yaml = Selma::Sanitizer.from_yaml(sanitizer.to_yaml)
+
+      assert_equal(sanitizer.to_json, from_json.to_json)
+      assert_equal(
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite(html),
+        Selma::Rewriter.new(sanitizer: from_json).rewrite(html),
+      )
+      assert_equal(
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite(html),
+        Selma::Rewriter.new(sanitizer: from_yaml).rewrite(html),
+      )
+    end
+  end
+end
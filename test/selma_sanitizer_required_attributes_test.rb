@@ -0,0 +1,72 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerRequiredAttributesTest < Minitest::Test
+    describe "sanitization" do
+      context "when an element is missing its required attributes" do
+        def setup
+          @sanitizer = Selma::Sanitizer.new({
+            elements: ["a", "b", "img"],
+            attributes: { "a" => ["href", "title"], "img" => ["src", "alt"] },
+            protocols: { "a" => { "href" => ["https"] }, "img" => { "src" => ["https"] } },
+            required_attributes: {
+              "a" => ["href"],
+              "img" => { attributes: ["src"], action: :remove },
+            },
+          })
+        end
+
+        def test_it_unwraps_elements_by_default
+          assert_equal(
+            "foo <b>bar</b> baz",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('foo <a title="x"><b>bar</b></a> baz'),
+          )
+        end
+
+        def test_it_unwraps_elements_whose_required_attributes_were_sanitized_away
+          assert_equal(
+            "foo <b>bar</b> baz",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('foo <a href="javascript:alert(1)"><b>bar</b></a> baz'),
+          )
+        end
+
+        def test_it_removes_elements_when_asked_to
+          assert_equal(
+            "foo  baz",
+            Selma::Rewriter.new(sanitizer: @sanitizer).rewrite('foo <img alt="x" src="http://example.com/a.png"> baz'),
+          )
+        end
+
+        def test_it_keeps_elements_with_their_required_attributes
+          html = '<a href="https://example.com">foo</a><img src="https://example.com/a.png">'
+
+          assert_equal(html, Selma::Rewriter.new(sanitizer: @sanitizer).rewrite(html))
+        end
+      end
+
+      def test_it_can_require_any_attribute
+        sanitizer = Selma::Sanitizer.new({
+          elements: ["span"],
+          attributes: { "span" => ["title"] },
+          required_attributes: { "span" => { attributes: [], action: :remove } },
+        })
+
+        assert_equal(
+          '<span title="x">foo</span>',
+          Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<span title="x">foo</span><span class="y">bar</span>'),
+        )
+      end
+
+      def test_it_rejects_unknown_actions
+        assert_raises(Selma::ConfigError) do
+          Selma::Sanitizer.new({
+            elements: ["a"],
+            required_attributes: { "a" => { attributes: ["href"], action: :explode } },
+          })
+        end
+      end
+    end
+  end
+end
@@ -0,0 +1,33 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerSanitizeTest < Minitest::Test
+    def sanitizer
+      Selma::Sanitizer.new({ elements: ["b", "html", "body"] })
+    end
+
+    def test_it_sanitizes_without_a_rewriter
+      html = "<b>x</b><i>y</i><script>alert(1)</script>"
+
+      assert_equal("<b>x</b>y", sanitizer.sanitize(html))
+      assert_equal(Selma::Rewriter.new(sanitizer: sanitizer).rewrite(html), sanitizer.sanitize(html))
+    end
+
+    def test_it_sanitizes_documents
+      assert_equal(
+        "<!DOCTYPE html><html><head></head><body><b>x</b>y</body></html>",
+        sanitizer.sanitize_document("<!DOCTYPE html><html><body><b>x</b><i>y</i></body></html>"),
+      )
+    end
+
+    def test_it_keeps_changes_made_right_after_new
+      sanitizer = self.sanitizer
+      sanitizer.disallow_element(["b"])
+
+      assert_equal("x", sanitizer.sanitize("<b>x</b>"))
+      assert_equal("x", Selma::Rewriter.new(sanitizer: sanitizer).rewrite("<b>x</b>"))
+    end
+  end
+end
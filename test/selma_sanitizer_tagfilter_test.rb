@@ -0,0 +1,46 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerTagfilterTest < Minitest::Test
+    def test_it_removes_filtered_elements_with_their_contents
+      sanitizer = Selma::Sanitizer.new({ elements: ["b"] })
+
+      assert_equal("<b>y</b>", sanitizer.sanitize("<title>x</title><b>y</b>"))
+      assert_equal("", sanitizer.sanitize("<xmp><img src=x onerror=alert(1)></xmp>"))
+    end
+
+    def test_it_never_turns_raw_text_into_markup
+      sanitizer = Selma::Sanitizer.new({ elements: ["b"], escape_tagfilter: false })
+
+      assert_equal("&lt;img src=x onerror=alert(1)&gt;", sanitizer.sanitize("<xmp><img src=x onerror=alert(1)></xmp>"))
+      assert_equal("a &amp; &lt;b&gt;b&lt;/b&gt;", sanitizer.sanitize("<textarea>a &amp; <b>b</b></textarea>"))
+    end
+
+    # from the GitHub Flavored Markdown spec
+    def test_it_matches_the_gfm_tagfilter
+      sanitizer = Selma::Sanitizer.new({ elements: ["strong", "em", "blockquote"], escape_tagfilter: :gfm })
+
+      assert_equal(
+        "<strong> &lt;title> &lt;style> <em></em></strong>",
+        sanitizer.sanitize("<strong> <title> <style> <em></em></strong>"),
+      )
+      assert_equal(
+        "<blockquote>\n  &lt;xmp> is disallowed.  &lt;XMP> is also disallowed.\n</blockquote>",
+        sanitizer.sanitize("<blockquote>\n  <xmp> is disallowed.  <XMP> is also disallowed.\n</blockquote>"),
+      )
+    end
+
+    def test_the_gfm_tagfilter_escapes_end_tags
+      sanitizer = Selma::Sanitizer.new({ elements: ["b"], escape_tagfilter: :gfm })
+
+      assert_equal("<b>&lt;script>alert(1)&lt;/script></b>", sanitizer.sanitize("<b><script>alert(1)</script></b>"))
+      assert_equal("x&lt;/TextArea >", sanitizer.sanitize("<scripty>x</scripty></TextArea >"))
+    end
+
+    def test_it_validates_the_tagfilter
+      assert_raises(Selma::ConfigError) { Selma::Sanitizer.new({ escape_tagfilter: :commonmark }) }
+    end
+  end
+end
@@ -0,0 +1,43 @@
+# frozen_string_literal: true
+
+require "test_helper"
+
+module Selma
+  class SanitizerWildcardAttributesTest < Minitest::Test
+    def test_it_allows_data_and_aria_attributes
+      sanitizer = Selma::Sanitizer.new({
+        elements: ["span"],
+        attributes: { all: [:data], "span" => [:aria] },
+      })
+
+      assert_equal(
+        '<span data-foo="1" aria-label="bar">foo</span>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<span data-foo="1" aria-label="bar" title="baz">foo</span>'),
+      )
+    end
+
+    def test_it_allows_prefix_patterns
+      sanitizer = Selma::Sanitizer.new({
+        elements: ["span"],
+        attributes: { "span" => ["x-*"] },
+      })
+
+      assert_equal(
+        '<span x-foo="1">foo</span>',
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<span x-foo="1" x-="2" y-foo="3">foo</span>'),
+      )
+    end
+
+    def test_it_never_matches_dangerous_attributes
+      sanitizer = Selma::Sanitizer.new({
+        elements: ["span"],
+        attributes: { "span" => ["o*", "s*", "h*"] },
+      })
+
+      assert_equal(
+        "<span>foo</span>",
+        Selma::Rewriter.new(sanitizer: sanitizer).rewrite('<span onclick="alert(1)" style="color: red" srcdoc="bar" href="javascript:alert(1)">foo</span>'),
+      )
+    end
+  end
+end
@@ -30,16 +30,16 @@ STRINGS = {
     html: '<b>Lo<!-- comment -->rem</b> <a href="pants" title="foo" style="text-decoration: underline;">ipsum</a> <a href="http://foo.com/"><strong>dolor</strong></a> sit<br/>amet <style>.foo { color: #fff; }</style> <script>alert("hello world");</script>',
     default: "Lorem ipsum dolor sit amet  ",
     restricted: "<b>Lorem</b> ipsum <strong>dolor</strong> sit amet  ",
-    basic: '<b>Lorem</b> <a>ipsum</a> <a href="http://foo.com/"><strong>dolor</strong></a> sit<br/>amet  ',
-    relaxed: '<b>Lorem</b> <a title="foo" style="text-decoration: underline;">ipsum</a> <a href="http://foo.com/"><strong>dolor</strong></a> sit<br/>amet <style>.foo { color: #fff; }</style> ',
+    basic: '<b>Lorem</b> <a href="pants">ipsum</a> <a href="http://foo.com/"><strong>dolor</strong></a> sit<br/>amet  ',
+    relaxed: '<b>Lorem</b> <a href="pants" title="foo" style="text-decoration: underline;">ipsum</a> <a href="http://foo.com/"><strong>dolor</strong></a> sit<br/>amet <style>.foo { color: #fff; }</style> ',
   },
 
   malformed: {
     html: 'Lo<!-- comment -->rem</b> <a href=pants title="foo>ipsum <a href="http://foo.com/"><strong>dolor</a></strong> sit<br/>amet <script>alert("hello world");',
     default: "Lorem</b> dolor</strong> sit amet ",
     restricted: "Lorem</b> <strong>dolor</strong> sit amet ",
-    basic: "Lorem</b> <a><strong>dolor</a></strong> sit<br/>amet ",
-    relaxed: 'Lorem</b> <a title="foo&gt;ipsum &lt;a href="><strong>dolor</a></strong> sit<br/>amet ',
+    basic: 'Lorem</b> <a href="pants"><strong>dolor</a></strong> sit<br/>amet ',
+    relaxed: 'Lorem</b> <a href="pants" title="foo&gt;ipsum &lt;a href="><strong>dolor</a></strong> sit<br/>amet ',
   },
 
   unclosed: {
@@ -172,6 +172,46 @@ PROTOCOLS = {
     relaxed: "<img>",
   },
 
+  protocol_based_js_injection_named_entity_colon: {
+    html: '<a href="javascript&colon;alert(1)">foo</a>',
+    default: "foo",
+    restricted: "foo",
+    basic: "<a>foo</a>",
+    relaxed: "<a>foo</a>",
+  },
+
+  protocol_based_js_injection_embedded_tab: {
+    html: '<a href="java&Tab;script:alert(1)">foo</a>',
+    default: "foo",
+    restricted: "foo",
+    basic: "<a>foo</a>",
+    relaxed: "<a>foo</a>",
+  },
+
+  protocol_based_js_injection_embedded_newline: {
+    html: "<a href=\"java\nscript:alert(1)\">foo</a>",
+    default: "foo",
+    restricted: "foo",
+    basic: "<a>foo</a>",
+    relaxed: "<a>foo</a>",
+  },
+
+  protocol_based_js_injection_uppercase: {
+    html: '<a href="JAVASCRIPT:alert(1)">foo</a>',
+    default: "foo",
+    restricted: "foo",
+    basic: "<a>foo</a>",
+    relaxed: "<a>foo</a>",
+  },
+
+  protocol_relative: {
+    html: '<a href="//example.com/">foo</a>',
+    default: "foo",
+    restricted: "foo",
+    basic: '<a href="//example.com/">foo</a>',
+    relaxed: '<a href="//example.com/">foo</a>',
+  },
+
   protocol_whitespace: {
     html: '<a href=" http://example.com/"></a>',
     default: "",